@@ -0,0 +1,32 @@
+//! Measures how fast a pool gets through a large number of short tasks.
+//!
+//! Run with `cargo bench --bench completions`.
+
+use std::time::Instant;
+
+use futures::executor::block_on;
+use poolparty::StoppableThreadPool;
+
+const TASKS: usize = 200_000;
+const ROUNDS: usize = 5;
+
+fn main() {
+    for round in 1..=ROUNDS {
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        let started = Instant::now();
+        for _ in 0..TASKS {
+            pool.spawn(async { Ok(()) });
+        }
+        let spawned = started.elapsed();
+        block_on(pool.observe()).unwrap();
+        let elapsed = started.elapsed();
+        println!(
+            "round {}: {} tasks in {:?} ({:.0} tasks/s), {:?} of it observing",
+            round,
+            TASKS,
+            elapsed,
+            TASKS as f64 / elapsed.as_secs_f64(),
+            elapsed - spawned
+        );
+    }
+}
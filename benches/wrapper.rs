@@ -0,0 +1,54 @@
+//! Measures the overhead of the per-task wrapper, comparing tiny tasks on a bare `ThreadPool` with the same tasks on a `StoppableThreadPool`.
+//!
+//! Run with `cargo bench --bench wrapper`.
+
+use std::{
+    sync::mpsc,
+    time::{Duration, Instant},
+};
+
+use futures::executor::{block_on, ThreadPool};
+use poolparty::StoppableThreadPool;
+
+const TASKS: usize = 200_000;
+const ROUNDS: usize = 5;
+
+fn bare(executor: &ThreadPool) -> Duration {
+    let (tx, rx) = mpsc::channel::<()>();
+    let started = Instant::now();
+    for _ in 0..TASKS {
+        let tx = tx.clone();
+        executor.spawn_ok(async move {
+            tx.send(()).unwrap();
+        });
+    }
+    for _ in 0..TASKS {
+        rx.recv().unwrap();
+    }
+    started.elapsed()
+}
+
+fn wrapped(executor: &ThreadPool) -> Duration {
+    let mut pool = StoppableThreadPool::<String>::new_with_pool(executor.clone());
+    let started = Instant::now();
+    for _ in 0..TASKS {
+        pool.spawn(async { Ok(()) });
+    }
+    block_on(pool.observe()).unwrap();
+    started.elapsed()
+}
+
+fn main() {
+    let executor = ThreadPool::new().unwrap();
+    for round in 1..=ROUNDS {
+        let bare = bare(&executor);
+        let wrapped = wrapped(&executor);
+        println!(
+            "round {}: bare {:?}, wrapped {:?}, {:.0} ns overhead per task",
+            round,
+            bare,
+            wrapped,
+            (wrapped.as_nanos() as f64 - bare.as_nanos() as f64) / TASKS as f64
+        );
+    }
+}
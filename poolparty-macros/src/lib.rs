@@ -0,0 +1,131 @@
+//! Attribute macros for `poolparty`, enabled by its `macros` feature.
+//!
+//! The generated code only consists of ordinary `StoppableThreadPool` calls.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, spanned::Spanned, Error, ItemFn, Path, ReturnType, Type};
+
+/// Mark an `async fn` without arguments returning a `Result<(), E>` as a task for `#[poolparty::main]`.
+///
+/// The function is left as is, the attribute only checks its signature.
+#[proc_macro_attribute]
+pub fn task(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let function = parse_macro_input!(item as ItemFn);
+    let mut errors = Vec::new();
+    if !attr.is_empty() {
+        errors.push(Error::new(
+            proc_macro2::TokenStream::from(attr).span(),
+            "#[poolparty::task] does not take any arguments",
+        ));
+    }
+    errors.extend(check_task(&function).err());
+    let errors = errors.iter().map(Error::to_compile_error);
+    quote!(#(#errors)* #function).into()
+}
+
+/// Generate a `main` which spawns the given tasks into a `StoppableThreadPool`, observes it and exits with code 1 on failure.
+///
+/// ```ignore
+/// #[poolparty::main(error = MyError, tasks(read, write))]
+/// fn main() {
+///     // Runs before the tasks are spawned.
+/// }
+/// ```
+///
+/// The body of the annotated function runs before the tasks are spawned.
+/// A failure is printed to stderr using its `Debug` implementation, like a `main` returning a `Result`.
+#[proc_macro_attribute]
+pub fn main(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let function = parse_macro_input!(item as ItemFn);
+    let mut error: Option<Type> = None;
+    let mut tasks: Vec<Path> = Vec::new();
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("error") {
+            error = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("tasks") {
+            meta.parse_nested_meta(|task| {
+                tasks.push(task.path);
+                Ok(())
+            })
+        } else {
+            Err(meta.error("expected `error = Type` or `tasks(..)`"))
+        }
+    });
+    parse_macro_input!(attr with parser);
+
+    // The function is emitted along with an error, so it does not cause follow-up errors.
+    let fail = |message: &str| {
+        let error = Error::new(function.sig.span(), message).to_compile_error();
+        quote!(#error #function).into()
+    };
+    let error = match error {
+        Some(error) => error,
+        None => {
+            return fail("missing the task error type: #[poolparty::main(error = MyError, ..)]")
+        }
+    };
+    if function.sig.asyncness.is_some() || !function.sig.inputs.is_empty() {
+        return fail("#[poolparty::main] expects a plain `fn` without arguments");
+    }
+
+    let (attrs, vis, ident, block) = (
+        &function.attrs,
+        &function.vis,
+        &function.sig.ident,
+        &function.block,
+    );
+    quote!(
+        #(#attrs)*
+        #vis fn #ident() {
+            #block
+            let mut pool = ::poolparty::StoppableThreadPool::<#error>::new()
+                .expect("failed to create the thread pool");
+            #(pool.spawn(#tasks());)*
+            if let Err(error) = ::poolparty::__private::block_on(pool.observe()) {
+                eprintln!("Error: {:?}", error);
+                ::std::process::exit(1);
+            }
+        }
+    )
+    .into()
+}
+
+fn check_task(function: &ItemFn) -> Result<(), Error> {
+    let sig = &function.sig;
+    if sig.asyncness.is_none() {
+        return Err(Error::new(
+            sig.fn_token.span(),
+            "#[poolparty::task] expects an `async fn`",
+        ));
+    }
+    if !sig.inputs.is_empty() {
+        return Err(Error::new(
+            sig.inputs.span(),
+            "#[poolparty::task] functions can't take arguments",
+        ));
+    }
+    let returns_result = match &sig.output {
+        ReturnType::Type(_, ty) => match &**ty {
+            Type::Path(path) => path
+                .path
+                .segments
+                .last()
+                .is_some_and(|segment| segment.ident == "Result"),
+            _ => false,
+        },
+        ReturnType::Default => false,
+    };
+    if !returns_result {
+        let span = match &sig.output {
+            ReturnType::Type(_, ty) => ty.span(),
+            ReturnType::Default => sig.ident.span(),
+        };
+        return Err(Error::new(
+            span,
+            "#[poolparty::task] functions must return `Result<(), E>`",
+        ));
+    }
+    Ok(())
+}
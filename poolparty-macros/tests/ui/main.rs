@@ -0,0 +1,12 @@
+#[poolparty::task]
+async fn ok() -> Result<(), String> {
+    Ok(())
+}
+
+#[poolparty::task]
+async fn also_ok() -> Result<(), String> {
+    Ok(())
+}
+
+#[poolparty::main(error = String, tasks(ok, also_ok))]
+fn main() {}
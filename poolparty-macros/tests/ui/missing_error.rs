@@ -0,0 +1,7 @@
+#[poolparty::task]
+async fn ok() -> Result<(), String> {
+    Ok(())
+}
+
+#[poolparty::main(tasks(ok))]
+fn main() {}
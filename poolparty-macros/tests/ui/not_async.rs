@@ -0,0 +1,11 @@
+#[poolparty::task]
+fn blocking() -> Result<(), String> {
+    Ok(())
+}
+
+#[poolparty::task]
+async fn with_argument(_value: u32) -> Result<(), String> {
+    Ok(())
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+#[poolparty::task]
+async fn unit() {}
+
+#[poolparty::task]
+async fn number() -> u32 {
+    42
+}
+
+fn main() {}
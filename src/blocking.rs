@@ -0,0 +1,63 @@
+use futures::{executor::block_on, future::Future};
+
+use crate::StoppableThreadPool;
+
+/// Block the calling thread on `future`, panicking if this is noticed to happen from within an asynchronous task.
+///
+/// The check is best-effort: it catches `async-std` tasks and nested futures executors, but not every runtime.
+fn block_on_sync<F: Future>(method: &str, future: F) -> F::Output {
+    assert!(
+        async_std::task::try_current().is_none(),
+        "{} must not be called from an asynchronous task, await the async version instead",
+        method
+    );
+    block_on(future)
+}
+
+impl<PoolError, StopReason> StoppableThreadPool<PoolError, StopReason>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Send + Sync + 'static,
+{
+    /// Same as `stop()`, blocking the calling thread instead. Do not call this from an asynchronous context.
+    ///
+    /// Panics if called from within a task of `async-std` or from within a futures executor.
+    pub fn stop_blocking(&self, why: StopReason) {
+        block_on_sync("stop_blocking()", self.stop(why))
+    }
+}
+
+impl<PoolError, StopReason> StoppableThreadPool<PoolError, StopReason>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Into<PoolError> + Send + Sync + 'static,
+{
+    /// Same as `observe()`, blocking the calling thread instead. Do not call this from an asynchronous context.
+    ///
+    /// Blocking a worker thread of the pool itself could deadlock it, so this panics if called from within a task of `async-std` or from within a futures executor.
+    pub fn observe_blocking(&self) -> Result<(), PoolError> {
+        block_on_sync("observe_blocking()", self.observe())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::future::pending;
+
+    use crate::StoppableThreadPool;
+
+    #[test]
+    fn blocking_shims() {
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        pool.spawn(pending());
+        pool.stop_blocking("stop".to_string());
+        assert_eq!(pool.observe_blocking(), Err("stop".to_string()));
+    }
+
+    #[test]
+    #[should_panic(expected = "must not be called from an asynchronous task")]
+    fn blocking_in_async_context() {
+        let pool = StoppableThreadPool::<String>::new().unwrap();
+        async_std::task::block_on(async { pool.observe_blocking() }).unwrap();
+    }
+}
@@ -0,0 +1,105 @@
+use std::io;
+
+use futures::{
+    executor::ThreadPool,
+    future::{Future, FutureExt},
+};
+
+use crate::{handle::Factory, StoppableThreadPool};
+
+/// A re-runnable set of task factories.
+///
+/// Every `run()` creates a fresh `StoppableThreadPool`, so stop requests or completions of one run can never leak into the next.
+pub struct PoolBlueprint<PoolError>
+where
+    PoolError: Send + Sync + 'static,
+{
+    pool: ThreadPool,
+    factories: Vec<Factory<PoolError>>,
+}
+
+impl<PoolError> PoolBlueprint<PoolError>
+where
+    PoolError: Send + Sync + 'static,
+{
+    /// Create a new `PoolBlueprint` instance using a default futures `ThreadPool` executor instance.
+    pub fn new() -> Result<PoolBlueprint<PoolError>, io::Error> {
+        Ok(PoolBlueprint::new_with_pool(ThreadPool::new()?))
+    }
+
+    /// Create a new `PoolBlueprint` instance using a user supplied futures `ThreadPool` executor instance.
+    ///
+    /// The executor is shared by all runs.
+    pub fn new_with_pool(pool: ThreadPool) -> PoolBlueprint<PoolError> {
+        PoolBlueprint {
+            pool,
+            factories: Vec::new(),
+        }
+    }
+
+    /// Add a task factory, which is called once per run to create the future to spawn.
+    pub fn add<F, Fut>(&mut self, factory: F) -> &mut Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), PoolError>> + Send + 'static,
+    {
+        self.factories.push(Box::new(move || factory().boxed()));
+        self
+    }
+
+    /// Spawn a fresh future from every factory to a new `StoppableThreadPool` and observe it.
+    pub async fn run(&self) -> Result<(), PoolError> {
+        StoppableThreadPool::run_with_pool(
+            self.pool.clone(),
+            self.factories.iter().map(|factory| factory()),
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use futures::{executor::block_on, future::pending};
+
+    use crate::PoolBlueprint;
+
+    fn first_run<T>(runs: &AtomicUsize, first: T, other: T) -> T {
+        match runs.fetch_add(1, Ordering::SeqCst) {
+            0 => first,
+            _ => other,
+        }
+    }
+
+    #[test]
+    fn run_repeatedly() {
+        let waiting = Arc::new(AtomicUsize::new(0));
+        let failing = Arc::new(AtomicUsize::new(0));
+        let mut blueprint = PoolBlueprint::new().unwrap();
+        blueprint
+            .add(|| async { Ok(()) })
+            .add(move || {
+                let wait = first_run(&waiting, true, false);
+                async move {
+                    if wait {
+                        pending::<()>().await;
+                    }
+                    Ok(())
+                }
+            })
+            .add(move || {
+                let output = first_run(&failing, Err("first run failed".to_string()), Ok(()));
+                async move { output }
+            });
+
+        block_on(async {
+            assert_eq!(blueprint.run().await.unwrap_err(), "first run failed");
+            assert_eq!(blueprint.run().await.unwrap(), ());
+            assert_eq!(blueprint.run().await.unwrap(), ());
+        });
+    }
+}
@@ -0,0 +1,101 @@
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+
+use crate::{fanout::FanOut, OutcomeKind, StoppableThreadPool, TaskId};
+
+/// Number of messages buffered for each bridge, see `StoppableThreadPool::bridge_outcomes()`.
+const BRIDGE_BUFFER: usize = 1024;
+
+/// A task which finished, as forwarded by `StoppableThreadPool::bridge_outcomes()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskOutcomeMessage {
+    task: TaskId,
+    context: Option<String>,
+    outcome: OutcomeKind,
+}
+
+impl TaskOutcomeMessage {
+    pub(crate) fn new(task: TaskId, context: Option<String>, outcome: OutcomeKind) -> Self {
+        Self {
+            task,
+            context,
+            outcome,
+        }
+    }
+
+    /// The task which finished.
+    pub fn task(&self) -> TaskId {
+        self.task
+    }
+
+    /// The context of the task, if it was spawned with one.
+    pub fn context(&self) -> Option<&str> {
+        self.context.as_deref()
+    }
+
+    /// How the task finished.
+    pub fn outcome(&self) -> OutcomeKind {
+        self.outcome
+    }
+}
+
+/// The std channels outcomes are forwarded to.
+pub(crate) type Bridges = FanOut<SyncSender<TaskOutcomeMessage>>;
+
+impl<PoolError, StopReason> StoppableThreadPool<PoolError, StopReason>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Send + Sync + 'static,
+{
+    /// Forward every task finishing from now on to a blocking `std::sync::mpsc` receiver, for synchronous consumers.
+    ///
+    /// The receiver disconnects once observing the pool finished, or once the pool was dropped.
+    /// Forwarding never blocks the pool: each bridge buffers up to 1024 messages, messages arriving while the buffer is full are dropped
+    /// for that bridge and counted by `dropped_outcomes()`.
+    pub fn bridge_outcomes(&self) -> Receiver<TaskOutcomeMessage> {
+        let shared = &self.spawner.shared;
+        let (tx, outcomes) = sync_channel(BRIDGE_BUFFER);
+        shared.bridges.add(tx);
+        if shared.is_finished() {
+            // There is nothing left to forward.
+            shared.bridges.close();
+        }
+        outcomes
+    }
+
+    /// The number of messages dropped because the buffer of a bridge was full, see `bridge_outcomes()`.
+    pub fn dropped_outcomes(&self) -> usize {
+        self.spawner.shared.bridges.dropped()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{executor::block_on, future::pending};
+
+    use crate::{OutcomeKind, StoppableThreadPool, TaskId};
+
+    #[test]
+    fn bridged_outcomes() {
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        let outcomes = pool.bridge_outcomes();
+        pool.spawn_with_context("upload", async { Ok(()) })
+            .spawn(async { Ok(()) });
+
+        // The consumer ends once observing finished.
+        let consumer = std::thread::spawn(move || outcomes.iter().collect::<Vec<_>>());
+        assert_eq!(block_on(pool.observe()), Ok(()));
+
+        let mut outcomes = consumer.join().unwrap();
+        outcomes.sort_by_key(|message| message.task());
+        assert_eq!(outcomes.len(), 2);
+        assert_eq!(outcomes[0].task(), TaskId(0));
+        assert_eq!(outcomes[0].context(), Some("upload"));
+        assert_eq!(outcomes[1].outcome(), OutcomeKind::Completed);
+
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        pool.spawn(pending());
+        block_on(pool.stop("stop".to_string()));
+        assert!(block_on(pool.observe()).is_err());
+        assert!(pool.bridge_outcomes().recv().is_err());
+    }
+}
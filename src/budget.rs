@@ -0,0 +1,232 @@
+use std::{
+    cell::Cell,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU8, AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+
+use futures::future::{poll_fn, Future};
+
+use crate::{StoppableThreadPool, TaskId, TaskOptions, CANCELLED};
+
+thread_local! {
+    /// What is left of the budget of the task being polled on this thread, `None` outside of budgeted tasks.
+    static BUDGET: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+/// Spend one unit of the poll budget of the current task, yielding back to the executor once it is used up.
+///
+/// Call this in loops which may go on for a long time without ever waiting, so a task spawned by `StoppableThreadPool::spawn_budgeted()`
+/// can't hold on to a worker thread: once the budget is spent the task yields, and the pool checks whether it was asked to stop before polling it again.
+/// Outside of budgeted tasks this completes right away.
+pub async fn consume_budget() {
+    poll_fn(|cx| {
+        BUDGET.with(|budget| match budget.get() {
+            Some(0) => {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+            Some(left) => {
+                budget.set(Some(left - 1));
+                Poll::Ready(())
+            }
+            None => Poll::Ready(()),
+        })
+    })
+    .await
+}
+
+/// Restores the budget of an enclosing task once the inner future was polled, even if it panicked.
+struct BudgetGuard(Option<usize>);
+
+impl Drop for BudgetGuard {
+    fn drop(&mut self) {
+        BUDGET.with(|budget| budget.set(self.0));
+    }
+}
+
+/// Grants the inner future `budget` units of `consume_budget()` on every poll, see `StoppableThreadPool::spawn_budgeted()`.
+struct Budgeted<Fut> {
+    future: Pin<Box<Fut>>,
+    budget: usize,
+    state: Arc<AtomicU8>,
+}
+
+impl<Fut: Future> Future for Budgeted<Fut> {
+    type Output = Fut::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // The stop signal wakes the task, which then drops this future.
+        if self.state.load(Ordering::Acquire) == CANCELLED {
+            return Poll::Pending;
+        }
+        let _guard = BudgetGuard(BUDGET.with(|budget| budget.replace(Some(self.budget))));
+        self.future.as_mut().poll(cx)
+    }
+}
+
+/// Fails with the error of `make_error` once the inner future was polled more than `limit` times, see `StoppableThreadPool::spawn_with_poll_limit()`.
+struct PollLimited<Fut, F> {
+    future: Pin<Box<Fut>>,
+    limit: usize,
+    polls: Arc<AtomicUsize>,
+    /// Boxed, so the closure needs not be `Unpin`.
+    make_error: Option<Box<F>>,
+}
+
+impl<Fut, F, PoolError> Future for PollLimited<Fut, F>
+where
+    Fut: Future<Output = Result<(), PoolError>>,
+    F: FnOnce() -> PoolError,
+{
+    type Output = Fut::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.polls.load(Ordering::Acquire) == self.limit {
+            let make_error = self.make_error.take().expect("polled after completion");
+            return Poll::Ready(Err(make_error()));
+        }
+        self.polls.fetch_add(1, Ordering::AcqRel);
+        self.future.as_mut().poll(cx)
+    }
+}
+
+impl<PoolError, StopReason> StoppableThreadPool<PoolError, StopReason>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Send + Sync + 'static,
+{
+    /// Spawn a future which yields back to the executor once it called `consume_budget()` `poll_budget` times within a single poll.
+    ///
+    /// Before the future is polled again the pool checks whether it was asked to stop in the meantime,
+    /// so a loop calling `consume_budget()` can't hold on to a worker thread and is cancelled within `poll_budget` iterations even if it never waits.
+    /// This does not help against code which does not call `consume_budget()`.
+    ///
+    /// Panics if `poll_budget` is zero.
+    pub fn spawn_budgeted<Fut>(&mut self, future: Fut, poll_budget: usize) -> TaskId
+    where
+        Fut: Future<Output = Result<(), PoolError>> + Send + 'static,
+    {
+        assert!(poll_budget > 0, "the poll budget must not be zero");
+        let (id, registration) = self.spawner.register(None);
+        if let Some((stopped, state)) = registration {
+            let budgeted = Budgeted {
+                future: Box::pin(future),
+                budget: poll_budget,
+                state: state.clone(),
+            };
+            self.spawner
+                .launch(id, stopped, state, budgeted, TaskOptions::default());
+        }
+        id
+    }
+
+    /// Spawn a future which fails with the error of `make_error` once it was polled `limit` times without completing.
+    ///
+    /// Unlike a timeout, this catches tasks busy-polling due to a bug while leaving tasks alone that legitimately wait for a long time.
+    /// The failure stops the pool like any other. `task_polls()` reports how often the task was polled, so a very high limit can be used for diagnostics alone.
+    /// The count is deliberately not part of the `TaskOutcome`, which stays the same for all tasks.
+    pub fn spawn_with_poll_limit<Fut, F>(
+        &mut self,
+        limit: usize,
+        future: Fut,
+        make_error: F,
+    ) -> TaskId
+    where
+        Fut: Future<Output = Result<(), PoolError>> + Send + 'static,
+        F: FnOnce() -> PoolError + Send + 'static,
+    {
+        let (id, registration) = self.spawner.register(None);
+        if let Some((stopped, state)) = registration {
+            let polls = Arc::new(AtomicUsize::new(0));
+            self.spawner.shared.tasks.lock().unwrap()[id.0].polls = Some(polls.clone());
+            let limited = PollLimited {
+                future: Box::pin(future),
+                limit,
+                polls,
+                make_error: Some(Box::new(make_error)),
+            };
+            self.spawner
+                .launch(id, stopped, state, limited, TaskOptions::default());
+        }
+        id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        marker::PhantomPinned,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        task::Poll,
+        time::Duration,
+    };
+
+    use futures::{
+        executor::{block_on, ThreadPool},
+        future::{pending, poll_fn},
+    };
+
+    use super::consume_budget;
+    use crate::StoppableThreadPool;
+
+    #[test]
+    fn busy_task_is_cancelled() {
+        // The busy task occupies one thread, the other one is left to stop the pool.
+        let threads = ThreadPool::builder().pool_size(2).create().unwrap();
+        let mut pool = StoppableThreadPool::<String>::new_with_pool(threads);
+        let polls = Arc::new(AtomicUsize::new(0));
+        let counted = polls.clone();
+        pool.spawn_budgeted(
+            async move {
+                // Never waits, so only the budget gets it to yield.
+                while counted.fetch_add(1, Ordering::SeqCst) < usize::MAX {
+                    consume_budget().await;
+                }
+                Ok(())
+            },
+            4,
+        );
+
+        block_on(async {
+            while polls.load(Ordering::SeqCst) < 100 {
+                async_std::task::yield_now().await;
+            }
+            pool.stop("stop".to_string()).await;
+            assert_eq!(pool.observe().await, Err("stop".to_string()));
+            assert_eq!(pool.await_stopped(Duration::from_secs(5)).await, Ok(()));
+        });
+        // Completes right away outside of budgeted tasks.
+        block_on(consume_budget());
+    }
+
+    #[test]
+    fn poll_limit_exceeded() {
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        pool.spawn(pending());
+        let busy = pool.spawn_with_poll_limit(
+            8,
+            poll_fn(|cx| {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }),
+            {
+                // Closures which are not `Unpin` are accepted as well.
+                let pinned = PhantomPinned;
+                move || {
+                    let _ = &pinned;
+                    "busy".to_string()
+                }
+            },
+        );
+
+        assert_eq!(block_on(pool.observe()), Err("busy".to_string()));
+        assert_eq!(pool.task_polls(busy), Some(8));
+    }
+}
@@ -0,0 +1,188 @@
+use std::{
+    io,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures::{
+    executor::ThreadPool,
+    future::{BoxFuture, Future},
+};
+
+use crate::{Clock, StoppableThreadPool, TaskId, TaskOutcome};
+
+/// What a pool created by `StoppableThreadPool::new_chaotic()` does to its tasks.
+#[derive(Debug, Clone)]
+pub struct ChaosConfig {
+    /// Tasks wait a random delay of up to this long before they are polled for the first time.
+    pub max_delay: Duration,
+    /// The fraction of tasks, from `0.0` to `1.0`, which are cancelled spuriously after a random number of polls.
+    ///
+    /// Such tasks are reported as cancelled, their future is dropped like after a stop signal.
+    pub cancel_probability: f64,
+    /// The number of polls after which a spuriously cancelled task is dropped is chosen from `0..=max_polls`.
+    pub max_polls: usize,
+    /// Send the stop signal to the tasks in random order instead of the order they were spawned in.
+    pub shuffle_stop: bool,
+}
+
+impl Default for ChaosConfig {
+    fn default() -> Self {
+        ChaosConfig {
+            max_delay: Duration::from_millis(1),
+            cancel_probability: 0.0,
+            max_polls: 4,
+            shuffle_stop: true,
+        }
+    }
+}
+
+/// Pools which use the task error type as stop reason, too.
+impl<PoolError> StoppableThreadPool<PoolError>
+where
+    PoolError: Send + Sync + 'static,
+{
+    /// Create a new `StoppableThreadPool` instance for testing, which disturbs its tasks as described by `config`.
+    ///
+    /// All random decisions are derived from `seed` and the id of the task concerned,
+    /// so the same seed cancels the same tasks after the same number of polls and shuffles the stop signals the same way.
+    /// Only the delays are subject to timing, as they are by nature.
+    pub fn new_chaotic(
+        seed: u64,
+        config: ChaosConfig,
+    ) -> Result<StoppableThreadPool<PoolError>, io::Error> {
+        let mut pool = StoppableThreadPool::new_with_pool(ThreadPool::new()?);
+        let shared = Arc::get_mut(&mut pool.spawner.shared).expect("the pool was just created");
+        shared.chaos = Some(Chaos {
+            seed,
+            config,
+            clock: shared.clock.clone(),
+        });
+        Ok(pool)
+    }
+}
+
+pub(crate) struct Chaos {
+    seed: u64,
+    config: ChaosConfig,
+    clock: Arc<dyn Clock>,
+}
+
+impl Chaos {
+    fn rng(&self, salt: u64) -> fastrand::Rng {
+        fastrand::Rng::with_seed(self.seed ^ salt.wrapping_mul(0x9e37_79b9_7f4a_7c15))
+    }
+
+    /// Wrap the future of the task `id`, delaying its first poll and possibly cancelling it.
+    pub(crate) fn disturb<PoolError, Fut>(&self, id: TaskId, future: Fut) -> Disturbed<Fut>
+    where
+        Fut: Future<Output = TaskOutcome<PoolError>>,
+    {
+        let mut rng = self.rng(id.0 as u64 + 1);
+        let delay = self.config.max_delay.mul_f64(rng.f64());
+        let cancelled = rng.f64() < self.config.cancel_probability;
+        let polls = rng.usize(0..=self.config.max_polls);
+        Disturbed {
+            delay: Some(self.clock.sleep(delay)),
+            polls_left: if cancelled { Some(polls) } else { None },
+            future: Box::pin(future),
+        }
+    }
+
+    /// Reorder the tasks the stop signal is sent to, the same way for the same pool.
+    pub(crate) fn shuffle(&self, order: &mut [usize]) {
+        if self.config.shuffle_stop {
+            self.rng(0).shuffle(order);
+        }
+    }
+}
+
+pub(crate) struct Disturbed<Fut> {
+    delay: Option<BoxFuture<'static, ()>>,
+    polls_left: Option<usize>,
+    future: Pin<Box<Fut>>,
+}
+
+impl<Fut, PoolError> Future for Disturbed<Fut>
+where
+    Fut: Future<Output = TaskOutcome<PoolError>>,
+{
+    type Output = TaskOutcome<PoolError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Some(delay) = self.delay.as_mut() {
+            futures::ready!(delay.as_mut().poll(cx));
+            self.delay = None;
+        }
+        match self.polls_left.as_mut() {
+            Some(0) => return Poll::Ready(TaskOutcome::Cancelled),
+            Some(polls) => *polls -= 1,
+            None => (),
+        }
+        self.future.as_mut().poll(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::{executor::block_on, future::join_all};
+
+    use super::ChaosConfig;
+    use crate::{StoppableThreadPool, TaskOutcome};
+
+    fn cancelled_tasks(seed: u64) -> Vec<usize> {
+        let mut pool = StoppableThreadPool::<String>::new_chaotic(
+            seed,
+            ChaosConfig {
+                cancel_probability: 0.5,
+                ..ChaosConfig::default()
+            },
+        )
+        .unwrap();
+        let handles: Vec<_> = (0..32)
+            .map(|_| {
+                pool.spawn_with_handle(async {
+                    for _ in 0..8 {
+                        async_std::task::yield_now().await;
+                    }
+                    Ok(())
+                })
+            })
+            .collect();
+        block_on(pool.observe()).unwrap();
+        block_on(join_all(handles))
+            .into_iter()
+            .enumerate()
+            .filter(|(_, outcome)| matches!(outcome, TaskOutcome::Cancelled))
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    #[test]
+    fn reproducible_from_seed() {
+        let cancelled = cancelled_tasks(7);
+        assert!(!cancelled.is_empty() && cancelled.len() < 32);
+        assert_eq!(cancelled_tasks(7), cancelled);
+    }
+
+    #[test]
+    fn failure_still_stops() {
+        let mut pool = StoppableThreadPool::new_chaotic(
+            1,
+            ChaosConfig {
+                max_delay: Duration::from_millis(5),
+                ..ChaosConfig::default()
+            },
+        )
+        .unwrap();
+        for _ in 0..16 {
+            pool.spawn(futures::future::pending());
+        }
+        pool.spawn(async { Err("fail".to_string()) });
+        assert_eq!(block_on(pool.observe()), Err("fail".to_string()));
+    }
+}
@@ -0,0 +1,199 @@
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+    task::{Poll, Waker},
+    time::Duration,
+};
+
+// `std::time::Instant::now()` panics on wasm targets, elsewhere both are the same type.
+#[cfg(not(feature = "wasm"))]
+pub(crate) use std::time::Instant;
+#[cfg(feature = "wasm")]
+pub(crate) use web_time::Instant;
+
+use futures::{
+    future::{poll_fn, BoxFuture, Future, FutureExt},
+    pin_mut, select,
+};
+
+/// The source of time for timeouts, start rates, grace periods and task durations of a pool.
+///
+/// `SystemClock` is used unless the pool is created by `StoppableThreadPool::new_with_clock()`, for example with a `MockClock` in tests.
+pub trait Clock: Send + Sync + 'static {
+    /// The current instant.
+    ///
+    /// With the `wasm` feature this is a `web_time::Instant`, which is the same as `std::time::Instant` on all targets but wasm.
+    fn now(&self) -> Instant;
+
+    /// A future completing once `duration` passed.
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()>;
+}
+
+/// The wall-clock time, sleeping on the `async-std` timer.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    #[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        async_std::task::sleep(duration).boxed()
+    }
+
+    #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        // The timers of the browser are not `Send`, so the sleep runs on the event loop and reports through a channel.
+        let (tx, rx) = futures::channel::oneshot::channel();
+        wasm_bindgen_futures::spawn_local(async move {
+            async_std::task::sleep(duration).await;
+            let _ = tx.send(());
+        });
+        rx.map(drop).boxed()
+    }
+}
+
+/// A clock for tests which only moves forward when `advance()` is called.
+///
+/// Clones share the same time, so one clone can be given to the pool while the test advances another one.
+#[derive(Clone)]
+pub struct MockClock {
+    inner: Arc<Mutex<MockTime>>,
+}
+
+struct MockTime {
+    now: Instant,
+    sleepers: Vec<Waker>,
+}
+
+impl MockClock {
+    /// Create a clock standing still at the current instant.
+    pub fn new() -> MockClock {
+        MockClock {
+            inner: Arc::new(Mutex::new(MockTime {
+                now: Instant::now(),
+                sleepers: Vec::new(),
+            })),
+        }
+    }
+
+    /// Move the time forward by `duration`, completing all sleeps which are due.
+    pub fn advance(&self, duration: Duration) {
+        let sleepers = {
+            let mut time = self.inner.lock().unwrap();
+            time.now += duration;
+            std::mem::take(&mut time.sleepers)
+        };
+        // Sleeps which are not due yet register again when polled.
+        for sleeper in sleepers {
+            sleeper.wake();
+        }
+    }
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        MockClock::new()
+    }
+}
+
+impl fmt::Debug for MockClock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockClock")
+            .field("now", &self.now())
+            .finish()
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.inner.lock().unwrap().now
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        let inner = self.inner.clone();
+        let deadline = self.now() + duration;
+        poll_fn(move |cx| {
+            let mut time = inner.lock().unwrap();
+            if time.now >= deadline {
+                return Poll::Ready(());
+            }
+            // Polled again before the next `advance()`, the waker is still registered.
+            if !time
+                .sleepers
+                .iter()
+                .any(|sleeper| sleeper.will_wake(cx.waker()))
+            {
+                time.sleepers.push(cx.waker().clone());
+            }
+            Poll::Pending
+        })
+        .boxed()
+    }
+}
+
+/// Run `future` for up to `duration` of `clock`, `None` if it did not complete in time.
+pub(crate) async fn timeout<Fut: Future>(
+    clock: &dyn Clock,
+    duration: Duration,
+    future: Fut,
+) -> Option<Fut::Output> {
+    let future = future.fuse();
+    let expired = clock.sleep(duration).fuse();
+    pin_mut!(future, expired);
+    select! {
+        output = future => Some(output),
+        () = expired => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{task::Context, time::Duration};
+
+    use futures::{executor::block_on, future::pending, FutureExt};
+
+    use super::{Clock, MockClock};
+    use crate::{StoppableThreadPool, TaskOutcome};
+
+    #[test]
+    fn mock_timeout() {
+        let clock = MockClock::new();
+        let mut pool = StoppableThreadPool::<String>::new_with_clock(
+            futures::executor::ThreadPool::new().unwrap(),
+            clock.clone(),
+        );
+        pool.spawn_with_timeout(Duration::from_secs(3600), pending());
+        let handle = pool.spawn_with_handle(pending());
+
+        let start = clock.now();
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(clock.now() - start, Duration::from_secs(60));
+        // The timeout only starts once the task was polled.
+        while pool.progress().get().cancelled() == 0 {
+            clock.advance(Duration::from_secs(3600));
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        block_on(async {
+            pool.stop("stop".to_string()).await;
+            assert_eq!(pool.observe().await, Err("stop".to_string()));
+            assert!(matches!(handle.await, TaskOutcome::Cancelled));
+        });
+    }
+
+    #[test]
+    fn repolled_sleep_registers_once() {
+        let clock = MockClock::new();
+        let mut sleep = clock.sleep(Duration::from_secs(1));
+        let waker = futures::task::noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        for _ in 0..10 {
+            assert!(sleep.poll_unpin(&mut cx).is_pending());
+        }
+        assert_eq!(clock.inner.lock().unwrap().sleepers.len(), 1);
+        clock.advance(Duration::from_secs(1));
+        assert!(sleep.poll_unpin(&mut cx).is_ready());
+    }
+}
@@ -0,0 +1,124 @@
+use std::{
+    fmt,
+    sync::{atomic::Ordering, Arc},
+};
+
+use async_std::channel::Sender;
+use futures::future::Future;
+
+use crate::{Message, Shared, TaskId};
+
+/// Lets a task stop the whole pool from the inside, handed out by `StoppableThreadPool::spawn_with_control()`.
+///
+/// Only the first stop request issued to a pool takes effect, whether it comes from a task or from `StoppableThreadPool::stop()`.
+pub struct TaskControl<PoolError, StopReason = PoolError> {
+    id: TaskId,
+    control_sender: Sender<Message<PoolError, StopReason>>,
+    shared: Arc<Shared<PoolError, StopReason>>,
+}
+
+impl<PoolError, StopReason> Clone for TaskControl<PoolError, StopReason> {
+    fn clone(&self) -> Self {
+        TaskControl {
+            id: self.id,
+            control_sender: self.control_sender.clone(),
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<PoolError, StopReason> TaskControl<PoolError, StopReason> {
+    pub(crate) fn new(
+        id: TaskId,
+        control_sender: Sender<Message<PoolError, StopReason>>,
+        shared: Arc<Shared<PoolError, StopReason>>,
+    ) -> Self {
+        TaskControl {
+            id,
+            control_sender,
+            shared,
+        }
+    }
+
+    /// The id of the task this handle was given to.
+    pub fn id(&self) -> TaskId {
+        self.id
+    }
+
+    /// Same as `StoppableThreadPool::is_stopping()`.
+    pub fn is_stopping(&self) -> bool {
+        self.shared.is_stopping()
+    }
+
+    /// Same as `StoppableThreadPool::is_draining()`.
+    pub fn is_draining(&self) -> bool {
+        self.shared.draining.load(Ordering::Acquire)
+    }
+
+    /// Completes once the pool started draining, see `StoppableThreadPool::begin_draining()`.
+    pub fn draining(&self) -> impl Future<Output = ()> + Send + 'static {
+        let signal = self.shared.drain_signal.1.clone();
+        async move {
+            // Nothing is ever sent, the channel is closed to signal.
+            let _ = signal.recv().await;
+        }
+    }
+
+    /// Stop all tasks of the pool, `observe()` then reports `Cause::StoppedByTask` with `why`.
+    ///
+    /// Returns `false` if the pool was stopping already, in which case `why` is dropped.
+    pub fn stop(&self, why: StopReason) -> bool {
+        self.request_stop(Some(why))
+    }
+
+    /// Stop all tasks of the pool as if they all completed, `observe()` then succeeds.
+    ///
+    /// Returns `false` if the pool was stopping already.
+    pub fn stop_ok(&self) -> bool {
+        self.request_stop(None)
+    }
+
+    /// Report a problem which does not stop the pool, see `StoppableThreadPool::warnings()`.
+    pub fn warn(&self, message: impl fmt::Display) {
+        self.shared.warnings.lock().unwrap().push(Warning {
+            task: self.id,
+            message: message.to_string(),
+        });
+    }
+
+    fn request_stop(&self, why: Option<StopReason>) -> bool {
+        if self.shared.stopping.swap(true, Ordering::AcqRel) {
+            return false;
+        }
+        // Fails only if the pool was dropped in the meantime.
+        let _ = self
+            .control_sender
+            .try_send(Message::StopByTask(self.id, why));
+        true
+    }
+}
+
+/// A non-fatal problem reported by a task through `TaskControl::warn()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    task: TaskId,
+    message: String,
+}
+
+impl Warning {
+    /// The task which reported the warning.
+    pub fn task(&self) -> TaskId {
+        self.task
+    }
+
+    /// What the task reported.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.task, self.message)
+    }
+}
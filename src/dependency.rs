@@ -0,0 +1,56 @@
+use std::{
+    error::Error,
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use async_std::channel::{unbounded, Receiver, Sender};
+
+use crate::TaskId;
+
+/// Lets dependent tasks wait for a task to finish, see `StoppableThreadPool::spawn_after_task()`.
+pub(crate) struct Completion {
+    /// Closed once the task finished.
+    done: (Sender<()>, Receiver<()>),
+    succeeded: AtomicBool,
+}
+
+impl Completion {
+    pub(crate) fn new() -> Completion {
+        Completion {
+            done: unbounded(),
+            succeeded: AtomicBool::new(false),
+        }
+    }
+
+    pub(crate) fn finish(&self, succeeded: bool) {
+        self.succeeded.store(succeeded, Ordering::Release);
+        self.done.0.close();
+    }
+
+    /// Wait for the task to finish, returning whether it completed successfully.
+    pub(crate) async fn wait(&self) -> bool {
+        // Nothing is ever sent, this returns once the channel was closed.
+        let _ = self.done.1.recv().await;
+        self.succeeded.load(Ordering::Acquire)
+    }
+}
+
+/// Why `StoppableThreadPool::spawn_after_task()` was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DependencyError {
+    /// The dependency is not a task of this pool.
+    ///
+    /// As dependencies must exist before their dependents, this also rules out dependency cycles.
+    UnknownTask(TaskId),
+}
+
+impl fmt::Display for DependencyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DependencyError::UnknownTask(task) => write!(f, "{} is not a task of this pool", task),
+        }
+    }
+}
+
+impl Error for DependencyError {}
@@ -0,0 +1,58 @@
+use std::sync::atomic::Ordering;
+
+use crate::{PoolEvent, StoppableThreadPool};
+
+impl<PoolError, StopReason> StoppableThreadPool<PoolError, StopReason>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Send + Sync + 'static,
+{
+    /// Warn the tasks that the pool is about to stop, the first phase of a two-phase shutdown.
+    ///
+    /// Nothing is cancelled: cooperative tasks notice the warning through `TaskControl::is_draining()` or `TaskControl::draining()`
+    /// and can wind down, exiting on their own counts as completing normally. Stop the pool afterwards with `stop()` or `stop_graceful()` to cancel the rest.
+    /// Publishes `PoolEvent::DrainingStarted` the first time, and `Failure::was_draining()` reports whether the pool stopped after this was called.
+    pub fn begin_draining(&self) {
+        let shared = &self.spawner.shared;
+        if shared.draining.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        shared.subscribers.send(|| PoolEvent::DrainingStarted);
+        // Closing the channel wakes every task awaiting `TaskControl::draining()`.
+        shared.drain_signal.0.close();
+    }
+
+    /// Whether `begin_draining()` was called.
+    pub fn is_draining(&self) -> bool {
+        self.spawner.shared.draining.load(Ordering::Acquire)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{executor::block_on, future::pending, StreamExt};
+
+    use crate::{PoolEvent, StoppableThreadPool};
+
+    #[test]
+    fn two_phase_shutdown() {
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        let events = pool.subscribe();
+        pool.spawn_with_control(|control| async move {
+            control.draining().await;
+            assert!(control.is_draining());
+            Ok(())
+        });
+        pool.spawn(pending());
+        pool.begin_draining();
+        assert!(pool.is_draining());
+
+        block_on(async {
+            pool.stop("stop".to_string()).await;
+            let failure = pool.observe_detailed().await.unwrap_err();
+            assert!(failure.was_draining());
+        });
+        let events: Vec<_> = block_on(events.collect());
+        assert!(events.contains(&PoolEvent::DrainingStarted));
+    }
+}
@@ -0,0 +1,276 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use async_std::channel::{bounded, Receiver, Sender};
+use futures::stream::Stream;
+
+use crate::{fanout::FanOut, Cause, StoppableThreadPool, TaskId, TaskOutcome};
+
+/// Number of events buffered for each subscriber, see `StoppableThreadPool::subscribe()`.
+const EVENT_BUFFER: usize = 1024;
+
+/// Something that happened in a `StoppableThreadPool`, as reported by `StoppableThreadPool::subscribe()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PoolEvent {
+    /// The task `task` was spawned, or respawned.
+    TaskSpawned {
+        task: TaskId,
+        context: Option<String>,
+    },
+    /// The task `task` finished.
+    TaskCompleted { task: TaskId, outcome: OutcomeKind },
+    /// The task `task` is still running `elapsed` after its first poll, see `StoppableThreadPool::set_slow_threshold()`.
+    TaskSlow { task: TaskId, elapsed: Duration },
+    /// The pool started draining, see `StoppableThreadPool::begin_draining()`.
+    DrainingStarted,
+    /// The pool decided to stop.
+    StopRequested { cause: CauseKind },
+    /// The stop signal was sent to the `cancelled` tasks which were still running.
+    StopBroadcastFinished { cancelled: usize },
+    /// Observing the pool finished, the subscription ends after this event.
+    PoolFinished { result: Result<(), CauseKind> },
+}
+
+/// The kind of a `TaskOutcome`, without the error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum OutcomeKind {
+    Completed,
+    Failed,
+    Cancelled,
+    Panicked,
+    TimedOut,
+}
+
+impl<PoolError> From<&TaskOutcome<PoolError>> for OutcomeKind {
+    fn from(outcome: &TaskOutcome<PoolError>) -> Self {
+        match outcome {
+            TaskOutcome::Completed => OutcomeKind::Completed,
+            TaskOutcome::Failed(_) => OutcomeKind::Failed,
+            TaskOutcome::Cancelled => OutcomeKind::Cancelled,
+            TaskOutcome::Panicked(_) => OutcomeKind::Panicked,
+            TaskOutcome::TimedOut => OutcomeKind::TimedOut,
+        }
+    }
+}
+
+/// The kind of a `Cause`, without the error or stop reason.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CauseKind {
+    TaskFailed(TaskId),
+    TaskPanicked(TaskId),
+    Stopped,
+    StoppedByTask(TaskId),
+    PoolDropped,
+    StoppedByPartner,
+    /// Stopped by `StoppableThreadPool::stop_ok()`, or by the task through `TaskControl::stop_ok()`.
+    StoppedOk(Option<TaskId>),
+    /// `StoppableThreadPool::observe_quorum()` found too few tasks left which could succeed.
+    QuorumUnreachable,
+    /// `StoppableThreadPool::observe_quorum()` saw enough tasks succeed, the rest are stopped.
+    QuorumReached,
+}
+
+impl<PoolError, StopReason> From<&Cause<PoolError, StopReason>> for CauseKind {
+    fn from(cause: &Cause<PoolError, StopReason>) -> Self {
+        match cause {
+            Cause::TaskFailed { task, .. } => CauseKind::TaskFailed(*task),
+            Cause::TaskPanicked { task, .. } => CauseKind::TaskPanicked(*task),
+            Cause::Stopped(_) => CauseKind::Stopped,
+            Cause::StoppedByTask { task, .. } => CauseKind::StoppedByTask(*task),
+            Cause::PoolDropped => CauseKind::PoolDropped,
+            Cause::StoppedByPartner { .. } => CauseKind::StoppedByPartner,
+        }
+    }
+}
+
+/// The senders of all subscriptions of a pool.
+pub(crate) type Subscribers = FanOut<Sender<PoolEvent>>;
+
+/// A stream of the events of a pool, see `StoppableThreadPool::subscribe()`.
+pub struct Subscription {
+    events: Receiver<PoolEvent>,
+}
+
+impl Stream for Subscription {
+    type Item = PoolEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<PoolEvent>> {
+        Pin::new(&mut self.events).poll_next(cx)
+    }
+}
+
+impl<PoolError, StopReason> StoppableThreadPool<PoolError, StopReason>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Send + Sync + 'static,
+{
+    /// Subscribe to the events of the pool from now on.
+    ///
+    /// Any number of subscriptions can be active. Each one buffers up to 1024 events,
+    /// events happening while the buffer is full are dropped for this subscription, so a slow subscriber never holds up the pool.
+    /// The stream ends after `PoolEvent::PoolFinished`, or once the pool was dropped.
+    pub fn subscribe(&self) -> Subscription {
+        let shared = &self.spawner.shared;
+        let (tx, events) = bounded(EVENT_BUFFER);
+        shared.subscribers.add(tx);
+        if shared.is_finished() {
+            // There is nothing left to report.
+            shared.subscribers.close();
+        }
+        Subscription { events }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::{executor::block_on, future::pending, stream::StreamExt};
+
+    use super::{CauseKind, OutcomeKind, PoolEvent};
+    use crate::{StoppableThreadPool, TaskId};
+
+    #[test]
+    fn subscribe() {
+        let mut pool = StoppableThreadPool::new().unwrap();
+        let events = pool.subscribe();
+        let (tx, rx) = async_std::channel::unbounded::<()>();
+        pool.spawn_with_context("idle", pending())
+            .spawn(async move {
+                rx.recv().await.unwrap();
+                Err("fail".to_string())
+            });
+        block_on(async {
+            assert_eq!(
+                events.take(2).collect::<Vec<_>>().await,
+                vec![
+                    PoolEvent::TaskSpawned {
+                        task: TaskId(0),
+                        context: Some("idle".to_string())
+                    },
+                    PoolEvent::TaskSpawned {
+                        task: TaskId(1),
+                        context: None
+                    },
+                ]
+            );
+        });
+
+        let events = pool.subscribe();
+        tx.try_send(()).unwrap();
+        assert_eq!(block_on(pool.observe()), Err("fail".to_string()));
+        let events: Vec<_> = block_on(events.collect());
+        let cause = CauseKind::TaskFailed(TaskId(1));
+        assert_eq!(
+            &events[..3],
+            &[
+                PoolEvent::TaskCompleted {
+                    task: TaskId(1),
+                    outcome: OutcomeKind::Failed
+                },
+                PoolEvent::StopRequested { cause },
+                PoolEvent::StopBroadcastFinished { cancelled: 1 },
+            ]
+        );
+        // The cancelled task may or may not report before the pool finished.
+        assert_eq!(
+            events.last(),
+            Some(&PoolEvent::PoolFinished { result: Err(cause) })
+        );
+    }
+
+    #[test]
+    fn slow_subscriber() {
+        let mut pool = StoppableThreadPool::new().unwrap();
+        let events = pool.subscribe();
+        pool.spawn_n(1000, |_| async { Ok::<(), String>(()) });
+        block_on(pool.observe()).unwrap();
+        // 2000 events did not fit, the pool finished regardless.
+        assert_eq!(block_on(events.count()), 1024);
+    }
+
+    #[test]
+    fn stopped_ok() {
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        pool.spawn(pending());
+        let events = pool.subscribe();
+        block_on(async {
+            pool.stop_ok().await;
+            assert_eq!(pool.observe().await, Ok(()));
+        });
+        let events: Vec<_> = block_on(events.collect());
+        assert_eq!(
+            events[0],
+            PoolEvent::StopRequested {
+                cause: CauseKind::StoppedOk(None)
+            }
+        );
+
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        let events = pool.subscribe();
+        let task = pool.spawn_with_control(|control| async move {
+            control.stop_ok();
+            pending().await
+        });
+        assert_eq!(block_on(pool.observe()), Ok(()));
+        let events: Vec<_> = block_on(events.collect());
+        assert!(events.contains(&PoolEvent::StopRequested {
+            cause: CauseKind::StoppedOk(Some(task))
+        }));
+        assert_eq!(
+            events.last(),
+            Some(&PoolEvent::PoolFinished { result: Ok(()) })
+        );
+    }
+
+    #[test]
+    fn stop_requested_before_broadcast() {
+        let position = |events: &[PoolEvent], pattern: fn(&PoolEvent) -> bool| {
+            events.iter().position(pattern).unwrap()
+        };
+        let stop_requested = |event: &PoolEvent| matches!(event, PoolEvent::StopRequested { .. });
+        let broadcast =
+            |event: &PoolEvent| matches!(event, PoolEvent::StopBroadcastFinished { .. });
+
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        pool.spawn(pending());
+        let events = pool.subscribe();
+        block_on(async {
+            pool.stop_graceful("stop".to_string(), Duration::from_secs(1))
+                .await;
+            assert_eq!(pool.observe().await, Err("stop".to_string()));
+        });
+        let events: Vec<_> = block_on(events.collect());
+        assert!(position(&events, stop_requested) < position(&events, broadcast));
+        // `observe()` acted upon the stop once more, without announcing it again.
+        assert_eq!(
+            events.iter().filter(|event| stop_requested(event)).count(),
+            1
+        );
+
+        let mut first = StoppableThreadPool::<String>::new().unwrap();
+        let mut second = StoppableThreadPool::<String>::new().unwrap();
+        first.spawn(async { Err("fail".to_string()) });
+        second.spawn(pending());
+        let events = second.subscribe();
+        assert_eq!(
+            block_on(StoppableThreadPool::observe_both(&first, &second)),
+            Err("fail".to_string())
+        );
+        drop(second);
+        let events: Vec<_> = block_on(events.collect());
+        assert_eq!(
+            events[position(&events, stop_requested)],
+            PoolEvent::StopRequested {
+                cause: CauseKind::StoppedByPartner
+            }
+        );
+        assert!(position(&events, stop_requested) < position(&events, broadcast));
+    }
+}
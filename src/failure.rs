@@ -0,0 +1,178 @@
+use std::{error::Error, fmt, sync::Arc};
+
+use crate::{BoxError, TaskId};
+
+/// Why a `StoppableThreadPool` stopped.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Cause<PoolError, StopReason = PoolError> {
+    /// The task `task` returned `error`.
+    TaskFailed { task: TaskId, error: PoolError },
+    /// The task `task` panicked with `message`.
+    TaskPanicked { task: TaskId, message: String },
+    /// `stop()` was called by the user.
+    Stopped(StopReason),
+    /// The task `task` stopped the pool through its `TaskControl`.
+    StoppedByTask { task: TaskId, why: StopReason },
+    /// The pool was dropped before all of its tasks completed.
+    PoolDropped,
+    /// `StoppableThreadPool::observe_both()` stopped the pool because the other pool failed with `error`.
+    StoppedByPartner { error: PoolError },
+}
+
+/// Detailed report of a pool that did not complete successfully, as returned by `observe_detailed()`.
+#[derive(Debug)]
+pub struct Failure<PoolError, StopReason = PoolError> {
+    pub(crate) cause: Cause<PoolError, StopReason>,
+    pub(crate) context: Option<String>,
+    pub(crate) cancelled: Vec<TaskId>,
+    pub(crate) pool: Option<Arc<str>>,
+    pub(crate) draining: bool,
+    #[cfg(feature = "backtrace")]
+    pub(crate) backtrace: Option<Box<std::backtrace::Backtrace>>,
+}
+
+impl<PoolError, StopReason> Failure<PoolError, StopReason> {
+    /// Why the pool stopped.
+    pub fn cause(&self) -> &Cause<PoolError, StopReason> {
+        &self.cause
+    }
+
+    /// The context of the failed task, if it was spawned with one.
+    pub fn context(&self) -> Option<&str> {
+        self.context.as_deref()
+    }
+
+    /// The name of the pool, if it was created with `StoppableThreadPool::named()`.
+    pub fn pool_name(&self) -> Option<&str> {
+        self.pool.as_deref()
+    }
+
+    /// The backtrace captured when the task failed or panicked, see the `backtrace` feature.
+    ///
+    /// It shows where the error or panic left the future of the task, not where the error value was constructed.
+    /// `None` if the pool did not stop because of a task, or if backtraces are disabled through `RUST_BACKTRACE` or `RUST_LIB_BACKTRACE`.
+    #[cfg(feature = "backtrace")]
+    pub fn backtrace(&self) -> Option<&std::backtrace::Backtrace> {
+        self.backtrace.as_deref()
+    }
+
+    /// Whether the pool was draining when it stopped, see `StoppableThreadPool::begin_draining()`.
+    pub fn was_draining(&self) -> bool {
+        self.draining
+    }
+
+    /// The tasks which had not completed yet and were sent the stop signal.
+    pub fn cancelled(&self) -> &[TaskId] {
+        &self.cancelled
+    }
+
+    /// The error of the task that caused the pool to stop, if a task failed.
+    pub fn error(&self) -> Option<&PoolError> {
+        match &self.cause {
+            Cause::TaskFailed { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+
+    /// The reason passed to `stop()`, if the pool was stopped by the user or one of its tasks.
+    pub fn stop_reason(&self) -> Option<&StopReason> {
+        match &self.cause {
+            Cause::Stopped(why) | Cause::StoppedByTask { why, .. } => Some(why),
+            _ => None,
+        }
+    }
+
+    /// Discard the details and return the task error or stop reason that caused the pool to stop, `None` if a task panicked or the pool was dropped.
+    pub fn into_error(self) -> Option<PoolError>
+    where
+        StopReason: Into<PoolError>,
+    {
+        match self.cause {
+            Cause::TaskFailed { error, .. } | Cause::StoppedByPartner { error } => Some(error),
+            Cause::Stopped(why) | Cause::StoppedByTask { why, .. } => Some(why.into()),
+            Cause::TaskPanicked { .. } | Cause::PoolDropped => None,
+        }
+    }
+}
+
+impl Failure<BoxError> {
+    /// Returns `true` if the task error or stop reason that caused the pool to stop is of type `E`.
+    pub fn is<E: Error + 'static>(&self) -> bool {
+        self.downcast_ref::<E>().is_some()
+    }
+
+    /// Returns a reference to the task error or stop reason that caused the pool to stop if it is of type `E`.
+    pub fn downcast_ref<E: Error + 'static>(&self) -> Option<&E> {
+        match &self.cause {
+            Cause::TaskFailed { error, .. }
+            | Cause::Stopped(error)
+            | Cause::StoppedByTask { why: error, .. }
+            | Cause::StoppedByPartner { error } => error.downcast_ref(),
+            _ => None,
+        }
+    }
+
+    /// Returns the task error or stop reason that caused the pool to stop if it is of type `E`, otherwise hands back the failure as is.
+    pub fn downcast<E: Error + 'static>(self) -> Result<E, Self> {
+        if !self.is::<E>() {
+            return Err(self);
+        }
+        match self.into_error().map(|error| error.downcast::<E>()) {
+            Some(Ok(error)) => Ok(*error),
+            _ => unreachable!("the error was checked to be of type E"),
+        }
+    }
+}
+
+impl<PoolError, StopReason> fmt::Display for Failure<PoolError, StopReason>
+where
+    PoolError: fmt::Display,
+    StopReason: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let task = |task: &TaskId| {
+            let mut located = task.to_string();
+            if let Some(context) = &self.context {
+                located.push_str(&format!(" ({})", context));
+            }
+            if let Some(pool) = &self.pool {
+                located.push_str(&format!(" in pool {}", pool));
+            }
+            located
+        };
+        let pool = match &self.pool {
+            Some(pool) => format!("pool {}", pool),
+            None => "pool".to_string(),
+        };
+        match &self.cause {
+            Cause::TaskFailed { task: id, error } => write!(f, "{} failed: {}", task(id), error),
+            Cause::TaskPanicked { task: id, message } => {
+                write!(f, "{} panicked: {}", task(id), message)
+            }
+            Cause::Stopped(why) => match &self.pool {
+                Some(_) => write!(f, "{} stopped: {}", pool, why),
+                None => write!(f, "stopped: {}", why),
+            },
+            Cause::StoppedByTask { task: id, why } => write!(f, "stopped by {}: {}", task(id), why),
+            Cause::PoolDropped => write!(f, "{} dropped before all tasks completed", pool),
+            Cause::StoppedByPartner { error } => {
+                write!(f, "{} stopped, its partner pool failed: {}", pool, error)
+            }
+        }
+    }
+}
+
+impl<PoolError, StopReason> Error for Failure<PoolError, StopReason>
+where
+    PoolError: Error + 'static,
+    StopReason: Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.cause {
+            Cause::TaskFailed { error, .. } | Cause::StoppedByPartner { error } => Some(error),
+            Cause::Stopped(why) | Cause::StoppedByTask { why, .. } => Some(why),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use std::sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Mutex,
+};
+
+/// What became of an item offered to one of the senders of a `FanOut`.
+pub(crate) enum Offered {
+    Sent,
+    /// The buffer of the receiver is full, the item is dropped for it.
+    Full,
+    /// The receiver is gone, the sender is removed.
+    Disconnected,
+}
+
+/// A channel sender a `FanOut` can hand items to without blocking.
+pub(crate) trait Outlet<T> {
+    fn offer(&self, item: T) -> Offered;
+}
+
+impl<T> Outlet<T> for async_std::channel::Sender<T> {
+    fn offer(&self, item: T) -> Offered {
+        match self.try_send(item) {
+            Ok(()) => Offered::Sent,
+            Err(async_std::channel::TrySendError::Full(_)) => Offered::Full,
+            Err(async_std::channel::TrySendError::Closed(_)) => Offered::Disconnected,
+        }
+    }
+}
+
+impl<T> Outlet<T> for std::sync::mpsc::SyncSender<T> {
+    fn offer(&self, item: T) -> Offered {
+        match self.try_send(item) {
+            Ok(()) => Offered::Sent,
+            Err(std::sync::mpsc::TrySendError::Full(_)) => Offered::Full,
+            Err(std::sync::mpsc::TrySendError::Disconnected(_)) => Offered::Disconnected,
+        }
+    }
+}
+
+/// Senders receiving a copy of every item, shared by the subscriptions and the bridges of a pool.
+pub(crate) struct FanOut<S> {
+    senders: Mutex<Vec<S>>,
+    /// Lets pools without receivers skip the lock.
+    active: AtomicBool,
+    /// Number of copies dropped because a buffer was full.
+    dropped: AtomicUsize,
+}
+
+impl<S> Default for FanOut<S> {
+    fn default() -> Self {
+        FanOut {
+            senders: Mutex::new(Vec::new()),
+            active: AtomicBool::new(false),
+            dropped: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl<S> FanOut<S> {
+    pub(crate) fn add(&self, sender: S) {
+        self.senders.lock().unwrap().push(sender);
+        self.active.store(true, Ordering::Release);
+    }
+
+    /// Hand the item to every sender without blocking, the item is only created if there is one.
+    pub(crate) fn send<T: Clone>(&self, item: impl FnOnce() -> T)
+    where
+        S: Outlet<T>,
+    {
+        if !self.active.load(Ordering::Acquire) {
+            return;
+        }
+        let mut senders = self.senders.lock().unwrap();
+        if senders.is_empty() {
+            return;
+        }
+        let item = item();
+        senders.retain(|sender| match sender.offer(item.clone()) {
+            Offered::Sent => true,
+            Offered::Full => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                true
+            }
+            Offered::Disconnected => false,
+        });
+    }
+
+    /// Drop all senders, their receivers then report the end.
+    pub(crate) fn close(&self) {
+        let mut senders = self.senders.lock().unwrap();
+        self.active.store(false, Ordering::Release);
+        senders.clear();
+    }
+
+    pub(crate) fn dropped(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,173 @@
+use std::{sync::atomic::Ordering, time::Duration};
+
+use crate::{clock::timeout, CauseKind, Message, StoppableThreadPool, INTERNAL_CHANNEL};
+
+/// How the tasks of a pool stopped by `StoppableThreadPool::stop_graceful()` ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StopSummary {
+    graceful: usize,
+    aborted: usize,
+}
+
+impl StopSummary {
+    /// The number of tasks stopped by this call which exited within the grace period, including their cleanup.
+    pub fn graceful(&self) -> usize {
+        self.graceful
+    }
+
+    /// The number of tasks stopped by this call which had not exited when the grace period was over.
+    pub fn aborted(&self) -> usize {
+        self.aborted
+    }
+}
+
+impl<PoolError, StopReason> StoppableThreadPool<PoolError, StopReason>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Send + Sync + 'static,
+{
+    /// Stop the pool like `stop()`, then give the tasks up to `grace` to finish before aborting them.
+    ///
+    /// Stopped tasks are dropped the next time they yield, so what runs on after the stop signal is the cleanup of tasks spawned by `spawn_with_cleanup()`.
+    /// Once `grace` is over, the cleanups of the tasks stopped by this call which are still running are dropped, the tasks are reported as cancelled nonetheless.
+    /// Cleanups of tasks stopped in another way are left alone. Tasks which never yield can't be aborted and count as aborted.
+    ///
+    /// The stop signal is sent right away, so this works with or without `observe()` being awaited.
+    /// An observing `observe()` then finishes with `why` as usual, but without being able to tell the cancelled tasks.
+    pub async fn stop_graceful(&self, why: StopReason, grace: Duration) -> StopSummary {
+        let shared = &self.spawner.shared;
+        shared.stopping.store(true, Ordering::Release);
+        // Sent ahead of the broadcast, so the pool becoming idle can't be observed as success.
+        self.spawner
+            .control_sender
+            .send(Message::Stop(why))
+            .await
+            .expect(INTERNAL_CHANNEL);
+        shared.announce_stop(CauseKind::Stopped);
+        let stopped = shared.broadcast_stop();
+
+        let _ = timeout(&*shared.clock, grace, self.exited(Some(&stopped))).await;
+        let tasks = shared.tasks.lock().unwrap();
+        let mut aborted = 0;
+        for id in &stopped {
+            let task = &tasks[id.0];
+            if !task.exited.load(Ordering::Acquire) {
+                task.abort.0.close();
+                aborted += 1;
+            }
+        }
+        StopSummary {
+            graceful: stopped.len() - aborted,
+            aborted,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicBool, AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use futures::{executor::block_on, future::pending};
+
+    use crate::StoppableThreadPool;
+
+    #[test]
+    fn stop_graceful() {
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        pool.spawn_with_cleanup(pending(), async {}, None)
+            .spawn_with_cleanup(pending(), pending(), None);
+
+        block_on(async {
+            let summary = pool
+                .stop_graceful("stop".to_string(), Duration::from_millis(20))
+                .await;
+            assert_eq!((summary.graceful(), summary.aborted()), (1, 1));
+            assert_eq!(pool.observe().await, Err("stop".to_string()));
+            // The aborted task is reported as cancelled, too.
+            let mut progress = pool.progress();
+            while progress.get().cancelled() < 2 {
+                progress.changed().await;
+            }
+        });
+    }
+
+    #[test]
+    fn stop_graceful_while_observed() {
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        pool.spawn_with_cleanup(
+            pending(),
+            async_std::task::sleep(Duration::from_millis(5)),
+            None,
+        );
+
+        let (observed, summary) = block_on(async {
+            futures::join!(
+                pool.observe(),
+                pool.stop_graceful("stop".to_string(), Duration::from_secs(5))
+            )
+        });
+        assert_eq!(observed, Err("stop".to_string()));
+        assert_eq!((summary.graceful(), summary.aborted()), (1, 0));
+    }
+
+    #[test]
+    fn straggling_cleanup_aborted() {
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        let polls = Arc::new(AtomicUsize::new(0));
+        let polled = polls.clone();
+        pool.spawn_with_cleanup(
+            pending(),
+            async move {
+                loop {
+                    polled.fetch_add(1, Ordering::SeqCst);
+                    async_std::task::sleep(Duration::from_millis(1)).await;
+                }
+            },
+            None,
+        );
+
+        block_on(async {
+            let summary = pool
+                .stop_graceful("stop".to_string(), Duration::from_millis(20))
+                .await;
+            assert_eq!((summary.graceful(), summary.aborted()), (0, 1));
+            assert_eq!(pool.await_stopped(Duration::from_secs(5)).await, Ok(()));
+            let aborted = polls.load(Ordering::SeqCst);
+            assert!(aborted > 0);
+            async_std::task::sleep(Duration::from_millis(20)).await;
+            assert_eq!(polls.load(Ordering::SeqCst), aborted);
+        });
+    }
+
+    #[test]
+    fn other_cleanups_left_alone() {
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        let cleaned = Arc::new(AtomicBool::new(false));
+        let done = cleaned.clone();
+        pool.spawn_with_cleanup(
+            pending(),
+            async move {
+                async_std::task::sleep(Duration::from_millis(30)).await;
+                done.store(true, Ordering::SeqCst);
+            },
+            None,
+        );
+        // Stopped before, so not aborted by `stop_graceful()`.
+        pool.spawner.shared.broadcast_stop();
+
+        block_on(async {
+            let summary = pool
+                .stop_graceful("stop".to_string(), Duration::from_millis(1))
+                .await;
+            assert_eq!((summary.graceful(), summary.aborted()), (0, 0));
+            assert_eq!(pool.await_stopped(Duration::from_secs(5)).await, Ok(()));
+        });
+        assert!(cleaned.load(Ordering::SeqCst));
+    }
+}
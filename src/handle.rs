@@ -0,0 +1,162 @@
+use std::{
+    error::Error,
+    fmt,
+    pin::Pin,
+    sync::atomic::Ordering,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures::{
+    channel::oneshot,
+    future::{pending, BoxFuture, Future, FutureExt, Map},
+};
+
+use crate::{spawner::Spawner, TaskId, TaskOutcome};
+
+pub(crate) type Factory<PoolError> =
+    Box<dyn Fn() -> BoxFuture<'static, Result<(), PoolError>> + Send + Sync>;
+
+/// Handle to a task spawned from a factory by `StoppableThreadPool::spawn_factory()`.
+pub struct TaskHandle<PoolError, StopReason = PoolError> {
+    id: TaskId,
+    spawner: Spawner<PoolError, StopReason>,
+    factory: Factory<PoolError>,
+}
+
+impl<PoolError, StopReason> TaskHandle<PoolError, StopReason>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Send + Sync + 'static,
+{
+    pub(crate) fn new(
+        id: TaskId,
+        spawner: Spawner<PoolError, StopReason>,
+        factory: Factory<PoolError>,
+    ) -> TaskHandle<PoolError, StopReason> {
+        TaskHandle {
+            id,
+            spawner,
+            factory,
+        }
+    }
+
+    /// The id of the task.
+    pub fn id(&self) -> TaskId {
+        self.id
+    }
+
+    /// How long the task ran the last time, see `StoppableThreadPool::task_duration()`.
+    pub fn duration(&self) -> Option<Duration> {
+        self.spawner.shared.task_duration(self.id)
+    }
+
+    /// Spawn a fresh future from the factory into the same pool, under the same id and context.
+    ///
+    /// The respawned task is stopped and observed like any other task.
+    /// Fails if the task is still running or if the pool is stopping; note that by default a failing task stops the pool, so only tasks which completed successfully can be respawned then.
+    pub fn respawn(&self) -> Result<(), RespawnError> {
+        self.spawner.respawn(self.id, (self.factory)())
+    }
+}
+
+/// Future resolving to the outcome of a task spawned by `StoppableThreadPool::spawn_with_handle()`.
+///
+/// The handle is `Send`, `Unpin` and `'static`, so it can be pushed into a `FuturesUnordered` for example.
+/// Dropping the handle does not affect the task.
+pub struct JoinHandle<PoolError, StopReason = PoolError> {
+    id: TaskId,
+    spawner: Spawner<PoolError, StopReason>,
+    outcome: oneshot::Receiver<TaskOutcome<PoolError>>,
+}
+
+impl<PoolError, StopReason> JoinHandle<PoolError, StopReason> {
+    pub(crate) fn new(
+        id: TaskId,
+        spawner: Spawner<PoolError, StopReason>,
+        outcome: oneshot::Receiver<TaskOutcome<PoolError>>,
+    ) -> Self {
+        JoinHandle {
+            id,
+            spawner,
+            outcome,
+        }
+    }
+
+    /// The id of the task.
+    pub fn id(&self) -> TaskId {
+        self.id
+    }
+
+    /// Transform the outcome the handle resolves to.
+    pub fn map_outcome<T, F>(self, f: F) -> Map<Self, F>
+    where
+        F: FnOnce(TaskOutcome<PoolError>) -> T,
+    {
+        self.map(f)
+    }
+}
+
+impl<PoolError, StopReason> JoinHandle<PoolError, StopReason>
+where
+    PoolError: Clone + Send + Sync + 'static,
+    StopReason: Send + Sync + 'static,
+{
+    /// Spawn a follow-up task into the same pool with the future `f` creates from the outcome of this task.
+    ///
+    /// The follow-up task is registered right away, so observing the pool waits for it, and stopped like any other task.
+    /// If the pool is stopping by the time this task finishes, the follow-up is cancelled without calling `f`.
+    pub fn then_spawn<F, Fut>(self, f: F) -> JoinHandle<PoolError, StopReason>
+    where
+        F: FnOnce(TaskOutcome<PoolError>) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(), PoolError>> + Send + 'static,
+    {
+        let spawner = self.spawner.clone();
+        let shared = spawner.shared.clone();
+        spawner.spawn_with_handle(async move {
+            let outcome = self.await;
+            if shared.stopping.load(Ordering::Acquire) {
+                // The stop signal takes it from here.
+                return pending().await;
+            }
+            f(outcome).await
+        })
+    }
+}
+
+impl<PoolError, StopReason> fmt::Debug for JoinHandle<PoolError, StopReason> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JoinHandle").field("id", &self.id).finish()
+    }
+}
+
+impl<PoolError, StopReason> Future for JoinHandle<PoolError, StopReason> {
+    type Output = TaskOutcome<PoolError>;
+
+    /// A task which never reported back, because it was spawned after the pool stopped or the pool was dropped, resolves as cancelled.
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.outcome)
+            .poll(cx)
+            .map(|outcome| outcome.unwrap_or(TaskOutcome::Cancelled))
+    }
+}
+
+/// Why `TaskHandle::respawn()` was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RespawnError {
+    /// The task has not finished yet.
+    Running,
+    /// The pool is stopping or was dropped.
+    Stopping,
+}
+
+impl fmt::Display for RespawnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RespawnError::Running => write!(f, "task is still running"),
+            RespawnError::Stopping => write!(f, "pool is stopping"),
+        }
+    }
+}
+
+impl Error for RespawnError {}
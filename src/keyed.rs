@@ -0,0 +1,132 @@
+use std::{
+    any::{Any, TypeId},
+    hash::{Hash, Hasher},
+    sync::atomic::Ordering,
+};
+
+use futures::future::Future;
+
+use crate::{StoppableThreadPool, TaskId, TaskOptions};
+
+/// A key of any type, as used by `StoppableThreadPool::spawn_keyed()`.
+trait DynKey: Send {
+    fn as_any(&self) -> &dyn Any;
+    fn eq_key(&self, other: &dyn DynKey) -> bool;
+    fn hash_key(&self, state: &mut dyn Hasher);
+}
+
+impl<K: Hash + Eq + Send + 'static> DynKey for K {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn eq_key(&self, other: &dyn DynKey) -> bool {
+        other.as_any().downcast_ref::<K>() == Some(self)
+    }
+
+    fn hash_key(&self, mut state: &mut dyn Hasher) {
+        // Equal values of different key types must not collide.
+        TypeId::of::<K>().hash(&mut state);
+        self.hash(&mut state);
+    }
+}
+
+/// The key of a task in flight, see `StoppableThreadPool::spawn_keyed()`.
+pub(crate) struct TaskKey(Box<dyn DynKey>);
+
+impl PartialEq for TaskKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.eq_key(&*other.0)
+    }
+}
+
+impl Eq for TaskKey {}
+
+impl Hash for TaskKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash_key(state)
+    }
+}
+
+impl<PoolError, StopReason> StoppableThreadPool<PoolError, StopReason>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Send + Sync + 'static,
+{
+    /// Spawn the future created by `factory` unless a task spawned with the same `key` is still in flight, returning the id of that task instead.
+    ///
+    /// The key becomes available again as soon as its task completed, failed or was cancelled.
+    /// Keys of different types never match, even if their values are equal.
+    /// A stopping or draining pool cancels the new task right away like any other spawn, without reserving the key.
+    pub fn spawn_keyed<K, F, Fut>(&mut self, key: K, factory: F) -> TaskId
+    where
+        K: Hash + Eq + Send + 'static,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<(), PoolError>> + Send + 'static,
+    {
+        let key = TaskKey(Box::new(key));
+        let (id, registration) = {
+            // Held while registering, so two callers can't both spawn under the same key.
+            let shared = &self.spawner.shared;
+            let mut keyed = shared.keyed.lock().unwrap();
+            let closed =
+                shared.stopping.load(Ordering::Acquire) || shared.sealed.load(Ordering::Acquire);
+            // Tasks of a closed pool may not have noticed the stop signal yet, so their keys are not handed out.
+            match keyed.get(&key) {
+                Some(id) if !closed => return *id,
+                _ => (),
+            }
+            let (id, registration) = self.spawner.register(None);
+            if registration.is_some() {
+                keyed.insert(key, id);
+            }
+            (id, registration)
+        };
+        if let Some((stopped, state)) = registration {
+            let shared = self.spawner.shared.clone();
+            let release = move |_: &_| {
+                shared.keyed.lock().unwrap().retain(|_, task| *task != id);
+            };
+            let options = TaskOptions {
+                on_outcome: Some(Box::new(release)),
+                ..TaskOptions::default()
+            };
+            self.spawner.launch(id, stopped, state, factory(), options);
+        }
+        id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{channel::oneshot, executor::block_on, future::pending};
+
+    use crate::StoppableThreadPool;
+
+    #[test]
+    fn keyed_spawns() {
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        let (tx, rx) = oneshot::channel::<()>();
+        let refresh = pool.spawn_keyed("tenant x", || async {
+            rx.await.map_err(|_| "cancelled".to_string())
+        });
+        assert_eq!(pool.spawn_keyed("tenant x", || async { Ok(()) }), refresh);
+        assert_ne!(pool.spawn_keyed("tenant y", || async { Ok(()) }), refresh);
+        assert_ne!(
+            pool.spawn_keyed(String::from("tenant x"), || async { Ok(()) }),
+            refresh
+        );
+
+        tx.send(()).unwrap();
+        assert_eq!(block_on(pool.observe()), Ok(()));
+        assert!(pool.spawner.shared.keyed.lock().unwrap().is_empty());
+
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        let hang = pool.spawn_keyed(1, pending);
+        block_on(pool.stop("stop".to_string()));
+        assert_ne!(pool.spawn_keyed(1, pending), hang);
+        assert_eq!(block_on(pool.observe()), Err("stop".to_string()));
+        assert_eq!(block_on(pool.await_termination(None)), Ok(()));
+        assert!(pool.spawner.shared.keyed.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,130 @@
+use std::{error::Error, fmt};
+
+use futures::{executor::ThreadPool, future::Future};
+
+use crate::{local::Executor, StoppableThreadPool, TaskId, TaskOptions};
+
+/// The pool has no lane of this name, see `StoppableThreadPool::spawn_on()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownLane {
+    lane: String,
+}
+
+impl UnknownLane {
+    /// The name of the lane which was not found.
+    pub fn lane(&self) -> &str {
+        &self.lane
+    }
+}
+
+impl fmt::Display for UnknownLane {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the pool has no lane called {}", self.lane)
+    }
+}
+
+impl Error for UnknownLane {}
+
+impl<PoolError, StopReason> StoppableThreadPool<PoolError, StopReason>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Send + Sync + 'static,
+{
+    /// Attach `pool` as a secondary executor called `lane`, for tasks spawned with `spawn_on()`.
+    ///
+    /// Tasks on a lane are observed and stopped together with all other tasks of the pool, they just run on different threads,
+    /// so heavy tasks can't starve latency-critical ones. Adding a lane under an existing name replaces its executor for tasks spawned from now on.
+    pub fn add_lane(&mut self, lane: impl Into<String>, pool: ThreadPool) -> &mut Self {
+        self.spawner
+            .shared
+            .lanes
+            .lock()
+            .unwrap()
+            .insert(lane.into(), pool);
+        self
+    }
+
+    /// Spawn a future onto the executor of `lane` instead of the main executor of the pool.
+    ///
+    /// Fails without registering the task if no lane called `lane` was added with `add_lane()`.
+    pub fn spawn_on<Fut>(&mut self, lane: &str, future: Fut) -> Result<TaskId, UnknownLane>
+    where
+        Fut: Future<Output = Result<(), PoolError>> + Send + 'static,
+    {
+        let executor = match self.spawner.shared.lanes.lock().unwrap().get(lane) {
+            Some(pool) => Executor::ThreadPool(pool.clone()),
+            None => {
+                return Err(UnknownLane {
+                    lane: lane.to_string(),
+                })
+            }
+        };
+        let (id, registration) = self.spawner.register(None);
+        if let Some((stopped, state)) = registration {
+            self.spawner.launch_on(
+                &executor,
+                id,
+                stopped,
+                state,
+                future,
+                TaskOptions::default(),
+            );
+        }
+        Ok(id)
+    }
+
+    /// Detach the executor of `lane` and hand it back, to shut it down.
+    ///
+    /// Tasks already running on the lane keep its executor alive until they finished and are still tracked by the pool.
+    pub fn remove_lane(&mut self, lane: &str) -> Option<ThreadPool> {
+        self.spawner.shared.lanes.lock().unwrap().remove(lane)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{
+        executor::{block_on, ThreadPool},
+        future::pending,
+    };
+
+    use crate::StoppableThreadPool;
+
+    #[test]
+    fn lanes() {
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        let fast = ThreadPool::builder()
+            .pool_size(1)
+            .name_prefix("fast-")
+            .create()
+            .unwrap();
+        pool.add_lane("fast", fast)
+            .spawn_on("fast", async {
+                match std::thread::current().name() {
+                    Some(name) if name.starts_with("fast-") => Ok(()),
+                    _ => Err("not on the lane".to_string()),
+                }
+            })
+            .unwrap();
+        let unknown = pool.spawn_on("slow", pending()).unwrap_err();
+        assert_eq!(unknown.lane(), "slow");
+        // Nothing was registered for the unknown lane.
+        assert_eq!(block_on(pool.observe()), Ok(()));
+
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        pool.add_lane("batch", ThreadPool::new().unwrap());
+        let (tx, rx) = async_std::channel::unbounded::<()>();
+        pool.spawn(pending());
+        pool.spawn_on("batch", async move {
+            let _alive = tx;
+            pending().await
+        })
+        .unwrap();
+        block_on(pool.stop("stop".to_string()));
+        assert_eq!(block_on(pool.observe()), Err("stop".to_string()));
+        // The stop reached the task on the lane as well, dropping it along with its sender.
+        assert!(block_on(rx.recv()).is_err());
+        assert!(pool.remove_lane("batch").is_some());
+        assert!(pool.remove_lane("batch").is_none());
+    }
+}
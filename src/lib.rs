@@ -1,35 +1,306 @@
-use std::io;
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    future::IntoFuture,
+    io,
+    sync::{
+        atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering},
+        Arc, Mutex, Weak,
+    },
+    time::Duration,
+};
 
 use async_std::channel::{unbounded, Receiver, Sender};
 
 use futures::{
     executor::ThreadPool,
-    future::{Future, FutureExt},
+    future::{BoxFuture, Future, FutureExt},
     pin_mut, select,
 };
 
+mod blocking;
+mod blueprint;
+mod bridge;
+mod budget;
+#[cfg(feature = "chaos")]
+mod chaos;
+mod clock;
+mod control;
+mod dependency;
+mod draining;
+mod events;
+mod failure;
+mod fanout;
+mod graceful;
+mod handle;
+mod keyed;
+mod lanes;
+mod link;
+mod local;
+mod mapped;
+#[cfg(feature = "test-util")]
+mod mock;
+mod order;
+mod outcome;
+mod parts;
+mod poll;
+mod progress;
+mod quorum;
+mod rate;
+mod registry;
+mod scope;
+mod sink;
+mod slow;
+mod spawner;
+mod stats;
+mod stoppable;
+mod stream;
+#[cfg(feature = "metrics")]
+mod telemetry;
+mod termination;
+mod typestate;
+#[cfg(feature = "wasm")]
+mod wasm;
+
+pub use blueprint::PoolBlueprint;
+pub use bridge::TaskOutcomeMessage;
+pub use budget::consume_budget;
+#[cfg(feature = "chaos")]
+pub use chaos::ChaosConfig;
+pub use clock::{Clock, MockClock, SystemClock};
+pub use control::{TaskControl, Warning};
+pub use dependency::DependencyError;
+pub use events::{CauseKind, OutcomeKind, PoolEvent, Subscription};
+pub use failure::{Cause, Failure};
+pub use graceful::StopSummary;
+pub use handle::{JoinHandle, RespawnError, TaskHandle};
+pub use lanes::UnknownLane;
+pub use link::PoolLink;
+pub use local::ExecutionMode;
+pub use mapped::MappedPool;
+#[cfg(feature = "test-util")]
+pub use mock::MockPool;
+pub use order::StopOrder;
+pub use outcome::TaskOutcome;
+pub use parts::{ControlMessage, ControlReceiver, PoolParts, StopTrigger, TaskWrapper};
+pub use progress::{Progress, ProgressWatch};
+pub use quorum::QuorumError;
+pub use registry::TaskInfo;
+pub use scope::{stoppable_scope, Scope};
+pub use sink::{SinkError, TaskSink};
+pub use slow::RunningTask;
+pub use spawner::{SwapPoolError, TrySpawnError};
+pub use stats::TaskDurations;
+pub use stoppable::StoppablePool;
+pub use typestate::{Building, Pool, Running};
+
+#[cfg(feature = "macros")]
+pub use poolparty_macros::{main, task};
+
+/// Used by the code `#[poolparty::main]` generates.
+#[cfg(feature = "macros")]
+#[doc(hidden)]
+pub mod __private {
+    pub use futures::executor::block_on;
+}
+
+use clock::Instant;
+use events::Subscribers;
+use handle::Factory;
+use link::{StopHook, Unlink};
+use local::{Executor, LocalTasks};
+use progress::ProgressState;
+use rate::StartRate;
+use spawner::{Spawner, TaskOptions};
+use termination::Exits;
+
+/// Convenience error type for pools running tasks with different error types.
+///
+/// The concrete error can be recovered from a `Failure<BoxError>` using `Failure::downcast()`.
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
 const INTERNAL_CHANNEL: &str = "Control channel closed, this should never happen.";
 
+/// Identifies a task spawned to a `StoppableThreadPool`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TaskId(usize);
+
+impl fmt::Display for TaskId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "task #{}", self.0)
+    }
+}
+
+/// Sent to `observe()` over the control channel.
+///
+/// Successful and cancelled tasks only count down `Shared::outstanding` instead of sending a message,
+/// so failures and stop requests never queue up behind the completions of other tasks.
+enum Message<PoolError, StopReason> {
+    /// A task failed or panicked.
+    Completed(TaskId, TaskOutcome<PoolError>),
+    Stop(StopReason),
+    /// `stop_ok()` was called, the pool should succeed.
+    StopOk,
+    /// A task requested the stop, with a reason unless the pool should succeed.
+    StopByTask(TaskId, Option<StopReason>),
+    /// The last outstanding task finished without failing.
+    Idle,
+    /// The partner pool of `observe_both()` failed with this error.
+    PartnerFailed(PoolError),
+}
+
+const RUNNING: u8 = 0;
+const COMPLETED: u8 = 1;
+const CANCELLED: u8 = 2;
+
+struct Task {
+    stop: Sender<()>,
+    state: Arc<AtomicU8>,
+    context: Option<String>,
+    /// How long the task ran from its first poll until it completed or was cancelled.
+    duration: Option<Duration>,
+    completion: Arc<dependency::Completion>,
+    /// Whether the wrapper of the task exited, see `await_stopped()`.
+    exited: Arc<AtomicBool>,
+    /// Closed to drop the cleanup of the task if it is still running, see `stop_graceful()`.
+    abort: (Sender<()>, Receiver<()>),
+    /// How often the task was polled, for tasks spawned with `spawn_with_poll_limit()`.
+    polls: Option<Arc<AtomicUsize>>,
+    /// See `set_stop_priority()`.
+    priority: i32,
+    /// When the task was first polled.
+    started: Arc<Mutex<Option<Instant>>>,
+    /// Captured when the task failed or panicked, see the `backtrace` feature.
+    #[cfg(feature = "backtrace")]
+    backtrace: Option<Box<std::backtrace::Backtrace>>,
+}
+
 /// Added functionality for the `futures::executor::ThreadPool` futures executor.
 ///
 /// Futures will be spawned to and executed by the internal and exchangeable `ThreadPool` instance, but in such a way that *all* spawned futures are asked to stop on user request or in case any of them returns an error.
 ///
 /// A notable difference to `futures:executor::ThreadPool` is that the user spawns futures of type `Output<Result(),T>` here instead of type `Output<()>`.
 ///
-/// Caveats: If you do not call `observe().await` once all desired futures are spawned or if you spawn additional futures after the first `observe().await` the stopping mechanism won't work. In other words, instances cannot be "reused" after they were being observed for the first time.
-/// For now no measures are in place to prevent a user from doing this (maybe in a future version).
+/// Caveats: The stopping mechanism only works while the pool is being observed, so call `observe().await` once all desired futures are spawned.
+/// Futures spawned after the pool stopped, because of `stop()` or a failing task, are never polled and count as cancelled.
+/// Futures spawned after observing completed successfully do run, but nothing stops them unless the pool is observed again.
+/// Use `Pool` to rule out spawning after observing started at compile time.
 ///
 /// Also note that spawned tasks *can not* be cancelled instantly. They will stop executing the next time they yield to the executor.
-pub struct StoppableThreadPool<PoolError>
+pub struct StoppableThreadPool<PoolError, StopReason = PoolError>
 where
     PoolError: Send + Sync + 'static,
+    StopReason: Send + Sync + 'static,
 {
-    pool: ThreadPool,
-    control_sender: Sender<Result<(), PoolError>>,
-    control_receiver: Receiver<Result<(), PoolError>>,
-    stop_senders: Vec<Sender<()>>,
+    spawner: Spawner<PoolError, StopReason>,
+    added: Mutex<Vec<BoxFuture<'static, Result<(), PoolError>>>>,
+    /// See `poll_observe()`, locked only to keep the pool `Sync`.
+    polled: Mutex<poll::Polled<PoolError, StopReason>>,
+}
+
+struct Shared<PoolError, StopReason> {
+    control_receiver: Receiver<Message<PoolError, StopReason>>,
+    tasks: Mutex<Vec<Task>>,
+    /// Number of tasks `observe()` still waits for.
+    ///
+    /// Tasks which finish without failing decrement it themselves and only report back once it drops to zero.
+    /// A failing task reports its outcome instead, leaving the decrement to `observe()`, so a failure is never missed as long as this is not zero.
+    outstanding: AtomicUsize,
+    /// Whether a `Message::Idle` is queued which `observe()` did not consume yet, so at most one is ever queued.
+    idle_queued: AtomicBool,
+    failed_task: Mutex<Option<TaskId>>,
+    stopping: AtomicBool,
+    /// Set while `observe_quorum()` runs, failures it tolerates must not mark the pool as stopping.
+    tolerant: AtomicBool,
+    /// Whether `PoolEvent::StopRequested` was published, so a stop acted upon twice announces itself once.
+    stop_announced: AtomicBool,
+    /// Set by `drain()`, tasks spawned afterwards are cancelled right away.
+    sealed: AtomicBool,
+    start_barrier: Mutex<Option<(Sender<()>, Receiver<()>)>>,
+    /// Messages taken off the control channel by `peek_error()` which `observe()` did not consume yet.
+    pending: Mutex<VecDeque<Message<PoolError, StopReason>>>,
+    /// Number of `observe()` calls currently waiting on the control channel.
+    observing: AtomicUsize,
+    /// Tasks to drive while observing, in local execution mode.
+    local: LocalTasks,
+    /// Run with the cause of a failure before the stop is broadcast, keyed by id for removal.
+    stop_hooks: Mutex<Vec<(usize, StopHook<PoolError, StopReason>)>>,
+    next_hook: AtomicUsize,
+    /// Remove the stop hooks linked pools hold towards this pool, run once it completed successfully.
+    unlinks: Mutex<Vec<Unlink>>,
+    progress: Arc<ProgressState>,
+    start_rate: Mutex<Option<Arc<StartRate>>>,
+    warnings: Mutex<Vec<Warning>>,
+    /// Report tasks which were never observed when the pool is dropped.
+    detect_leaks: AtomicBool,
+    /// Whether observing the pool ever ran to completion.
+    observed: AtomicBool,
+    /// Unfinished tasks above which `try_spawn()` refuses to spawn.
+    pending_limit: AtomicUsize,
+    clock: Arc<dyn Clock>,
+    subscribers: Subscribers,
+    exits: Arc<Exits>,
+    /// Awaited in order once observing finished, see `on_shutdown()`.
+    finalizers: Mutex<Vec<Finalizer<PoolError>>>,
+    shutdown_errors: Mutex<Vec<PoolError>>,
+    interceptor: Mutex<Option<Interceptor<PoolError>>>,
+    /// Task errors the interceptor decided to ignore.
+    ignored_errors: Mutex<Vec<(TaskId, PoolError)>>,
+    /// Number of errors ever ignored, unlike `ignored_errors` not reset by `take_ignored_errors()`.
+    ignored: AtomicUsize,
+    stop_order: Mutex<StopOrder>,
+    /// See `StoppableThreadPool::named()`.
+    name: Option<Arc<str>>,
+    /// `Some` if late completions are collected, see `collect_late_completions()`.
+    late_completions: Mutex<Option<Outcomes<PoolError>>>,
+    bridges: bridge::Bridges,
+    /// Tasks in flight by key, see `spawn_keyed()`.
+    keyed: Mutex<HashMap<keyed::TaskKey, TaskId>>,
+    /// Secondary executors by name, see `add_lane()`.
+    lanes: Mutex<HashMap<String, ThreadPool>>,
+    /// See `begin_draining()`.
+    draining: AtomicBool,
+    /// Closed once draining begins, nothing is ever sent.
+    drain_signal: (Sender<()>, Receiver<()>),
+    /// The size set by `resize()`, `0` if unknown.
+    pool_size: AtomicUsize,
+    /// See `set_slow_threshold()`.
+    slow_threshold: Mutex<Option<Duration>>,
+    #[cfg(feature = "metrics")]
+    metrics: std::sync::OnceLock<telemetry::PoolMetrics>,
+    #[cfg(feature = "chaos")]
+    chaos: Option<chaos::Chaos>,
+}
+
+type Interceptor<PoolError> = Arc<dyn Fn(&PoolError) -> ErrorDecision<PoolError> + Send + Sync>;
+
+/// What to do about a task error, as decided by the hook passed to `StoppableThreadPool::set_error_interceptor()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorDecision<PoolError> {
+    /// Stop the pool with the error, as without an interceptor.
+    StopPool,
+    /// Keep the pool running, the error can be retrieved with `take_ignored_errors()`.
+    Ignore,
+    /// Stop the pool with this error instead.
+    Transform(PoolError),
 }
 
+/// Report a failure as the plain error, resuming task panics, as `observe()` does.
+fn plain_result<PoolError, StopReason: Into<PoolError>>(
+    result: Result<(), Failure<PoolError, StopReason>>,
+) -> Result<(), PoolError> {
+    result.map_err(|failure| match failure.cause {
+        Cause::TaskPanicked { task, message } => panic!("{} panicked: {}", task, message),
+        _ => failure.into_error().expect(INTERNAL_CHANNEL),
+    })
+}
+
+/// Outcomes of tasks along with their ids.
+type Outcomes<PoolError> = Vec<(TaskId, TaskOutcome<PoolError>)>;
+
+type Finalizer<PoolError> = Box<dyn FnOnce() -> BoxFuture<'static, Result<(), PoolError>> + Send>;
+
+/// Pools which use the task error type as stop reason, too.
 impl<PoolError> StoppableThreadPool<PoolError>
 where
     PoolError: Send + Sync + 'static,
@@ -41,136 +312,2535 @@ where
 
     /// Create a new `StoppableThreadPool` instance using a user supplied futures `ThreadPool` executor instance.
     pub fn new_with_pool(pool: ThreadPool) -> StoppableThreadPool<PoolError> {
-        let (control_sender, control_receiver) = unbounded::<Result<(), PoolError>>();
-        StoppableThreadPool::<PoolError> {
-            pool,
-            control_sender,
-            control_receiver,
-            stop_senders: Vec::new(),
+        StoppableThreadPool::new_with_pool_and_reason(pool)
+    }
+
+    /// Create a new `StoppableThreadPool` instance using a user supplied futures `ThreadPool` executor instance, which takes the time from `clock`.
+    ///
+    /// Timeouts, start rates, grace periods and task durations all follow `clock`, so tests can use a `MockClock` instead of waiting.
+    pub fn new_with_clock(pool: ThreadPool, clock: impl Clock) -> StoppableThreadPool<PoolError> {
+        let mut pool = StoppableThreadPool::new_with_pool(pool);
+        Arc::get_mut(&mut pool.spawner.shared)
+            .expect("the pool was just created")
+            .clock = Arc::new(clock);
+        pool
+    }
+
+    /// Create a new `StoppableThreadPool` instance named `name`, using a new futures `ThreadPool` executor instance whose worker threads are named after the pool.
+    ///
+    /// The name shows up in the `Debug` output of the pool, its leak report and the `Failure` reports of `observe_detailed()`.
+    pub fn named(name: impl Into<String>) -> Result<StoppableThreadPool<PoolError>, io::Error> {
+        let name = name.into();
+        let executor = ThreadPool::builder()
+            .name_prefix(format!("{}-", name))
+            .create()?;
+        let mut pool = StoppableThreadPool::new_with_pool(executor);
+        Arc::get_mut(&mut pool.spawner.shared)
+            .expect("the pool was just created")
+            .name = Some(name.into());
+        Ok(pool)
+    }
+
+    /// Create a new `StoppableThreadPool` instance using a default futures `ThreadPool` executor instance,
+    /// falling back to local execution if the executor can't be created, for example if spawning threads is not permitted.
+    ///
+    /// Check `execution_mode()` to see which mode was selected, see `new_local()` for the implications of local execution.
+    pub fn new_with_fallback() -> StoppableThreadPool<PoolError> {
+        match ThreadPool::new() {
+            Ok(pool) => StoppableThreadPool::new_with_pool(pool),
+            Err(_) => StoppableThreadPool::new_local(),
+        }
+    }
+
+    /// Create a new `StoppableThreadPool` instance which executes its tasks on the thread observing the pool, without spawning any threads.
+    ///
+    /// Tasks are stopped and observed just the same, but they only make progress while the pool is being observed.
+    /// This includes cancelled tasks, which exit (and clean up) only once the pool is observed again or dropped.
+    pub fn new_local() -> StoppableThreadPool<PoolError> {
+        StoppableThreadPool::with_executor(Executor::Local)
+    }
+
+    /// Create a new `StoppableThreadPool` instance which executes its tasks on the global executor of `async-std`, without an executor of its own.
+    ///
+    /// This can't fail, as the global executor is started lazily. `with_pool()` still switches to a futures `ThreadPool` for tasks spawned afterwards.
+    #[cfg(feature = "global-executor")]
+    pub fn new_global() -> StoppableThreadPool<PoolError> {
+        StoppableThreadPool::with_executor(Executor::Global)
+    }
+
+    /// Create a new `StoppableThreadPool` instance which executes its tasks on the JavaScript event loop, through `wasm_bindgen_futures::spawn_local()`.
+    ///
+    /// This is the pool for `wasm32-unknown-unknown`, where no threads can be spawned. Tasks run whether or not the pool is observed,
+    /// use `spawn_local()` for futures which are not `Send`. It only works on wasm targets, elsewhere spawning panics.
+    #[cfg(feature = "wasm")]
+    pub fn new_wasm() -> StoppableThreadPool<PoolError> {
+        StoppableThreadPool::with_executor(Executor::Wasm)
+    }
+
+    /// Same as `run()` on a new `StoppableThreadPool` instance using the user supplied futures `ThreadPool` executor instance.
+    pub async fn run_with_pool<I, Fut>(pool: ThreadPool, tasks: I) -> Result<(), PoolError>
+    where
+        I: IntoIterator<Item = Fut>,
+        Fut: Future<Output = Result<(), PoolError>> + Send + 'static,
+    {
+        StoppableThreadPool::new_with_pool(pool).run(tasks).await
+    }
+}
+
+impl<PoolError, StopReason> StoppableThreadPool<PoolError, StopReason>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Send + Sync + 'static,
+{
+    /// Create a new `StoppableThreadPool` instance with a stop reason type distinct from the task error type, using a default futures `ThreadPool` executor instance.
+    pub fn new_with_reason() -> Result<StoppableThreadPool<PoolError, StopReason>, io::Error> {
+        Ok(StoppableThreadPool::new_with_pool_and_reason(
+            ThreadPool::new()?,
+        ))
+    }
+
+    /// Create a new `StoppableThreadPool` instance with a stop reason type distinct from the task error type, using a user supplied futures `ThreadPool` executor instance.
+    pub fn new_with_pool_and_reason(
+        pool: ThreadPool,
+    ) -> StoppableThreadPool<PoolError, StopReason> {
+        StoppableThreadPool::with_executor(Executor::ThreadPool(pool))
+    }
+
+    fn with_executor(executor: Executor) -> StoppableThreadPool<PoolError, StopReason> {
+        let (control_sender, control_receiver) = unbounded::<Message<PoolError, StopReason>>();
+        StoppableThreadPool {
+            spawner: Spawner {
+                executor,
+                control_sender,
+                shared: Arc::new(Shared {
+                    control_receiver,
+                    tasks: Mutex::new(Vec::new()),
+                    outstanding: AtomicUsize::new(0),
+                    idle_queued: AtomicBool::new(false),
+                    failed_task: Mutex::new(None),
+                    stopping: AtomicBool::new(false),
+                    tolerant: AtomicBool::new(false),
+                    stop_announced: AtomicBool::new(false),
+                    sealed: AtomicBool::new(false),
+                    start_barrier: Mutex::new(None),
+                    pending: Mutex::new(VecDeque::new()),
+                    observing: AtomicUsize::new(0),
+                    local: LocalTasks::default(),
+                    stop_hooks: Mutex::new(Vec::new()),
+                    next_hook: AtomicUsize::new(0),
+                    unlinks: Mutex::new(Vec::new()),
+                    progress: Arc::default(),
+                    start_rate: Mutex::new(None),
+                    warnings: Mutex::new(Vec::new()),
+                    detect_leaks: AtomicBool::new(false),
+                    observed: AtomicBool::new(false),
+                    pending_limit: AtomicUsize::new(usize::MAX),
+                    clock: Arc::new(SystemClock),
+                    subscribers: Subscribers::default(),
+                    exits: Arc::default(),
+                    finalizers: Mutex::new(Vec::new()),
+                    shutdown_errors: Mutex::new(Vec::new()),
+                    interceptor: Mutex::new(None),
+                    ignored_errors: Mutex::new(Vec::new()),
+                    ignored: AtomicUsize::new(0),
+                    stop_order: Mutex::default(),
+                    name: None,
+                    slow_threshold: Mutex::new(None),
+                    pool_size: AtomicUsize::new(0),
+                    bridges: bridge::Bridges::default(),
+                    keyed: Mutex::new(HashMap::new()),
+                    lanes: Mutex::new(HashMap::new()),
+                    draining: AtomicBool::new(false),
+                    drain_signal: unbounded(),
+                    late_completions: Mutex::new(None),
+                    #[cfg(feature = "metrics")]
+                    metrics: std::sync::OnceLock::new(),
+                    #[cfg(feature = "chaos")]
+                    chaos: None,
+                }),
+            },
+            added: Mutex::new(Vec::new()),
+            polled: Mutex::new(poll::Polled::NotStarted),
         }
     }
 
     /// Change the underlying futures `ThreadPool` executor instance.
-    pub fn with_pool(&mut self, pool: ThreadPool) -> &mut Self {
-        self.pool = pool;
+    ///
+    /// This switches a pool in local execution mode to the `ThreadPool` for tasks spawned from now on.
+    /// The swap is rejected while the pool is being observed, through a `PoolObserver` for example, handing `pool` back;
+    /// see `force_with_pool()` to swap anyway.
+    pub fn with_pool(&mut self, pool: ThreadPool) -> Result<&mut Self, SwapPoolError> {
+        if self.spawner.shared.observing.load(Ordering::SeqCst) > 0 {
+            return Err(SwapPoolError { pool });
+        }
+        Ok(self.force_with_pool(pool))
+    }
+
+    /// Change the underlying futures `ThreadPool` executor instance, even while the pool is being observed.
+    ///
+    /// Only tasks spawned from now on execute on `pool`. Tasks spawned before keep executing on the previous executor,
+    /// which they keep alive until they finished, so dropping it does not affect them. They are still observed and stopped like all other tasks.
+    pub fn force_with_pool(&mut self, pool: ThreadPool) -> &mut Self {
+        self.spawner.executor = Executor::ThreadPool(pool);
+        self.spawner.shared.pool_size.store(0, Ordering::Relaxed);
         self
     }
 
+    /// Swap in a new futures `ThreadPool` executor instance with `size` worker threads for the tasks spawned from now on.
+    ///
+    /// Unlike `with_pool()` this also works while the pool is being observed, which is when it is needed most.
+    /// Tasks spawned before keep executing on the previous executor, see `force_with_pool()`, and are still tracked like all other tasks.
+    /// The worker threads are named after the pool if it was created with `named()`.
+    ///
+    /// Panics if `size` is zero.
+    pub fn resize(&mut self, size: usize) -> Result<&mut Self, io::Error> {
+        let mut builder = ThreadPool::builder();
+        builder.pool_size(size);
+        if let Some(name) = &self.spawner.shared.name {
+            builder.name_prefix(format!("{}-", name));
+        }
+        self.force_with_pool(builder.create()?);
+        self.spawner.shared.pool_size.store(size, Ordering::Relaxed);
+        Ok(self)
+    }
+
+    /// The number of worker threads of the executor set by `resize()`.
+    ///
+    /// `None` if the executor was supplied by the user, sized by the futures default, or if the pool executes its tasks locally.
+    pub fn pool_size(&self) -> Option<usize> {
+        match self.spawner.shared.pool_size.load(Ordering::Relaxed) {
+            0 => None,
+            size => Some(size),
+        }
+    }
+
+    /// The name of the pool, if it was created with `named()`.
+    pub fn name(&self) -> Option<&str> {
+        self.spawner.shared.name.as_deref()
+    }
+
+    /// How the tasks spawned from now on are executed.
+    pub fn execution_mode(&self) -> ExecutionMode {
+        self.spawner.executor.mode()
+    }
+
     /// Start executing a future right away.
+    ///
+    /// If the pool is already stopping (`stop()` was called or a task failed), the future is dropped without ever being polled and the task counts as cancelled.
     pub fn spawn<Fut>(&mut self, future: Fut) -> &mut Self
     where
         Fut: Future<Output = Result<(), PoolError>> + Send + 'static,
     {
-        let (tx, rx) = unbounded::<()>();
-        self.stop_senders.push(tx);
-        let control = self.control_sender.clone();
-        self.pool.spawn_ok(async move {
-            let future = future.fuse();
-            let stopped = rx.recv().fuse();
-            pin_mut!(future, stopped);
-            let _ = select! {
-                output = future => control.send(output).await,
-                _ = stopped => control.send(Ok(())).await
-            };
-        });
+        self.spawn_task(future, None);
         self
     }
 
-    /// Ensure that all spawned tasks are canceled on individual task error or any ` stop()` request issued by the user.
-    /// Call this function once all tasks are spawned.
-    /// A task that fails before a call to `observe()` is being awaited will still trigger a stop as soon as you actually start awaiting here.
-    pub async fn observe(&self) -> Result<(), PoolError> {
-        let mut completed: usize = 0;
-        while let Ok(output) = self.control_receiver.recv().await {
-            completed += 1;
-            if output.is_err() {
-                for tx in self.stop_senders.iter() {
-                    if tx.send(()).await.is_err() {
-                        eprintln!("Task already finished")
-                    }
-                }
-                return output;
-            }
-            if completed == self.stop_senders.len() {
-                break;
+    /// Spawn a future unless the pool is at its pending limit, stopping or finished, handing the future back otherwise.
+    ///
+    /// On success this behaves exactly like `spawn()`. A refused future leaves no trace in the pool, it is not even counted as cancelled.
+    /// Fails with `TrySpawnError::Full` while the pool has as many unfinished tasks as allowed by `set_pending_limit()`,
+    /// and with `TrySpawnError::Stopped` once the pool is stopping, draining or finished.
+    pub fn try_spawn<Fut>(&mut self, future: Fut) -> Result<TaskId, TrySpawnError<Fut>>
+    where
+        Fut: Future<Output = Result<(), PoolError>> + Send + 'static,
+    {
+        let shared = &self.spawner.shared;
+        if shared.is_stopping() || shared.sealed.load(Ordering::Acquire) {
+            return Err(TrySpawnError::Stopped(future));
+        }
+        if shared.outstanding.load(Ordering::Acquire)
+            >= shared.pending_limit.load(Ordering::Relaxed)
+        {
+            return Err(TrySpawnError::Full(future));
+        }
+        // Checked again while registering, in case the pool stopped in the meantime.
+        let (id, (stopped, state)) = match self.spawner.try_register(None) {
+            Some(registration) => registration,
+            None => return Err(TrySpawnError::Stopped(future)),
+        };
+        self.spawner
+            .launch(id, stopped, state, future, TaskOptions::default());
+        Ok(id)
+    }
+
+    /// Limit the number of unfinished tasks for `try_spawn()`, which returns `TrySpawnError::Full` once the limit is reached.
+    ///
+    /// The other methods spawning tasks ignore the limit.
+    pub fn set_pending_limit(&mut self, limit: usize) -> &mut Self {
+        self.spawner
+            .shared
+            .pending_limit
+            .store(limit, Ordering::Relaxed);
+        self
+    }
+
+    /// Same as `spawn()`, but attach a context describing the task.
+    ///
+    /// If this task's error stops the pool, the context is reported alongside the error by `observe_detailed()`.
+    pub fn spawn_with_context<Fut>(&mut self, context: impl Into<String>, future: Fut) -> &mut Self
+    where
+        Fut: Future<Output = Result<(), PoolError>> + Send + 'static,
+    {
+        self.spawn_task(future, Some(context.into()));
+        self
+    }
+
+    /// Spawn `n` tasks created by `factory`, which receives the replica index (`0..n`).
+    ///
+    /// Returns the ids of the spawned tasks in replica order.
+    /// The replica index is recorded as the task context, see `task_context()`.
+    pub fn spawn_n<F, Fut>(&mut self, n: usize, mut factory: F) -> Vec<TaskId>
+    where
+        F: FnMut(usize) -> Fut,
+        Fut: Future<Output = Result<(), PoolError>> + Send + 'static,
+    {
+        (0..n)
+            .map(|index| self.spawn_task(factory(index), Some(format!("replica {}", index))))
+            .collect()
+    }
+
+    fn spawn_task<Fut>(&mut self, future: Fut, context: Option<String>) -> TaskId
+    where
+        Fut: Future<Output = Result<(), PoolError>> + Send + 'static,
+    {
+        self.spawner.spawn(
+            future,
+            TaskOptions {
+                context,
+                ..TaskOptions::default()
+            },
+        )
+    }
+
+    /// Spawn the future created by `f`, handing it a `TaskControl` to stop the whole pool from within the task.
+    ///
+    /// This is meant for stops which are not errors, such as "found the answer, nobody else needs to keep searching".
+    pub fn spawn_with_control<F, Fut>(&mut self, f: F) -> TaskId
+    where
+        F: FnOnce(TaskControl<PoolError, StopReason>) -> Fut,
+        Fut: Future<Output = Result<(), PoolError>> + Send + 'static,
+    {
+        let (id, registration) = self.spawner.register(None);
+        if let Some((stopped, state)) = registration {
+            let control = TaskControl::new(
+                id,
+                self.spawner.control_sender.clone(),
+                self.spawner.shared.clone(),
+            );
+            self.spawner
+                .launch(id, stopped, state, f(control), TaskOptions::default());
+        }
+        id
+    }
+
+    /// Spawn a future which only starts once all of `dependencies` completed successfully.
+    ///
+    /// The task is registered right away. If a dependency fails or is cancelled, the task is reported as cancelled without ever being polled.
+    /// Fails if a dependency is not a task of this pool; since dependencies have to be spawned first, there can't be any cycles.
+    pub fn spawn_after_task<I, Fut>(
+        &mut self,
+        dependencies: I,
+        future: Fut,
+    ) -> Result<TaskId, DependencyError>
+    where
+        I: IntoIterator<Item = TaskId>,
+        Fut: Future<Output = Result<(), PoolError>> + Send + 'static,
+    {
+        let dependencies = {
+            let tasks = self.spawner.shared.tasks.lock().unwrap();
+            dependencies
+                .into_iter()
+                .map(|id| match tasks.get(id.0) {
+                    Some(task) => Ok(task.completion.clone()),
+                    None => Err(DependencyError::UnknownTask(id)),
+                })
+                .collect::<Result<Vec<_>, _>>()?
+        };
+        Ok(self.spawner.spawn(
+            future,
+            TaskOptions {
+                dependencies,
+                ..TaskOptions::default()
+            },
+        ))
+    }
+
+    /// Same as `spawn()`, but give up on the task if it does not complete within `timeout`.
+    ///
+    /// A timed out task is dropped and reported as `TaskOutcome::TimedOut`; unlike an error, it does not stop the pool.
+    pub fn spawn_with_timeout<Fut>(&mut self, timeout: Duration, future: Fut) -> &mut Self
+    where
+        Fut: Future<Output = Result<(), PoolError>> + Send + 'static,
+    {
+        self.spawner.spawn(
+            future,
+            TaskOptions {
+                timeout: Some(timeout),
+                ..TaskOptions::default()
+            },
+        );
+        self
+    }
+
+    /// Same as `spawn()`, but run `cleanup` after the task was cancelled by the pool stopping.
+    ///
+    /// The task is dropped before `cleanup` starts, and the task is reported as cancelled once `cleanup` finished.
+    /// With a `cleanup_timeout`, a cleanup which does not finish in time is dropped so it can't hold up the shutdown.
+    /// `cleanup` is not run if the task completes on its own.
+    pub fn spawn_with_cleanup<Fut, Cleanup>(
+        &mut self,
+        future: Fut,
+        cleanup: Cleanup,
+        cleanup_timeout: Option<Duration>,
+    ) -> &mut Self
+    where
+        Fut: Future<Output = Result<(), PoolError>> + Send + 'static,
+        Cleanup: Future<Output = ()> + Send + 'static,
+    {
+        self.spawner.spawn(
+            future,
+            TaskOptions {
+                cleanup: Some(cleanup.boxed()),
+                cleanup_timeout,
+                ..TaskOptions::default()
+            },
+        );
+        self
+    }
+
+    /// Same as `spawn()`, but return a handle resolving to the outcome of the task.
+    ///
+    /// The pool observes the task like any other, the handle receives a clone of its outcome.
+    pub fn spawn_with_handle<Fut>(&mut self, future: Fut) -> JoinHandle<PoolError, StopReason>
+    where
+        PoolError: Clone,
+        Fut: Future<Output = Result<(), PoolError>> + Send + 'static,
+    {
+        self.spawner.spawn_with_handle(future)
+    }
+
+    /// Spawn a future created by `factory` and return a handle which can respawn the task later on, see `TaskHandle::respawn()`.
+    pub fn spawn_factory<F, Fut>(&mut self, factory: F) -> TaskHandle<PoolError, StopReason>
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), PoolError>> + Send + 'static,
+    {
+        let factory: Factory<PoolError> = Box::new(move || factory().boxed());
+        let id = self.spawn_task(factory(), None);
+        TaskHandle::new(id, self.spawner.clone(), factory)
+    }
+
+    /// Limit how fast tasks spawned from now on start: at most `per_second` tasks begin executing their future per second, after an initial burst of up to `burst` tasks.
+    ///
+    /// This throttles the ramp-up of the pool, not how many tasks run concurrently. Tasks waiting to start can be stopped like any other task.
+    ///
+    /// # Panics
+    ///
+    /// If `per_second` is zero.
+    pub fn set_start_rate(&mut self, per_second: u32, burst: u32) -> &mut Self {
+        assert!(per_second > 0, "the start rate must not be zero");
+        *self.spawner.shared.start_rate.lock().unwrap() = Some(Arc::new(StartRate::new(
+            per_second,
+            burst,
+            self.spawner.shared.clock.clone(),
+        )));
+        self
+    }
+
+    /// Let tasks spawned from now on wait at a shared start barrier before their future is polled for the first time.
+    ///
+    /// The barrier is released by `release()` or as soon as the pool is being observed.
+    /// Tasks which are stopped while waiting at the barrier never execute their future.
+    pub fn with_start_barrier(&mut self) -> &mut Self {
+        self.spawner
+            .shared
+            .start_barrier
+            .lock()
+            .unwrap()
+            .get_or_insert_with(unbounded);
+        self
+    }
+
+    /// Report on stderr if the pool is dropped with spawned tasks but was never observed to completion.
+    ///
+    /// Without observing, failures of the tasks go unnoticed and nothing cancels them.
+    /// The report includes the number of orphaned tasks and the contexts of those spawned with one.
+    pub fn detect_leaks(&mut self) -> &mut Self {
+        self.spawner
+            .shared
+            .detect_leaks
+            .store(true, Ordering::Relaxed);
+        self
+    }
+
+    /// Keep the outcomes of tasks which finished although they were already sent the stop signal, instead of dropping them.
+    ///
+    /// These tasks completed, failed or panicked before they noticed the signal, so their work may have finished despite the stop.
+    /// `observe()` returns before they report back, retrieve them with `take_late_completions()` once they exited, see `await_stopped()`.
+    pub fn collect_late_completions(&mut self) -> &mut Self {
+        self.spawner
+            .shared
+            .late_completions
+            .lock()
+            .unwrap()
+            .get_or_insert_with(Vec::new);
+        self
+    }
+
+    /// Take the late completions collected so far, see `collect_late_completions()`.
+    pub fn take_late_completions(&self) -> Vec<(TaskId, TaskOutcome<PoolError>)> {
+        match &mut *self.spawner.shared.late_completions.lock().unwrap() {
+            Some(late) => std::mem::take(late),
+            None => Vec::new(),
+        }
+    }
+
+    /// Decide about every task error with `interceptor` before it can stop the pool.
+    ///
+    /// The interceptor runs on the executor thread of the failed task, before the error reaches `observe()` and the stop is broadcast.
+    /// Panics and timeouts are not intercepted.
+    pub fn set_error_interceptor<F>(&mut self, interceptor: F) -> &mut Self
+    where
+        F: Fn(&PoolError) -> ErrorDecision<PoolError> + Send + Sync + 'static,
+    {
+        *self.spawner.shared.interceptor.lock().unwrap() = Some(Arc::new(interceptor));
+        self
+    }
+
+    /// Take the task errors which the error interceptor decided to ignore, along with the tasks that returned them.
+    pub fn take_ignored_errors(&self) -> Vec<(TaskId, PoolError)> {
+        std::mem::take(&mut *self.spawner.shared.ignored_errors.lock().unwrap())
+    }
+
+    /// Register a finalizer which is awaited once the pool finished, however it did, before observing returns the result.
+    ///
+    /// Finalizers run in the order they were registered, after all tasks were asked to stop, and only once even if observed again.
+    /// Their errors don't change the result of observing, retrieve them with `take_shutdown_errors()`.
+    pub fn on_shutdown<F, Fut>(&mut self, finalizer: F) -> &mut Self
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(), PoolError>> + Send + 'static,
+    {
+        self.spawner
+            .shared
+            .finalizers
+            .lock()
+            .unwrap()
+            .push(Box::new(move || finalizer().boxed()));
+        self
+    }
+
+    /// Take the errors returned by the finalizers registered with `on_shutdown()`.
+    pub fn take_shutdown_errors(&self) -> Vec<PoolError> {
+        std::mem::take(&mut *self.spawner.shared.shutdown_errors.lock().unwrap())
+    }
+
+    /// Whether the pool is stopping, because a stop was requested or a task failed, even if that was not observed yet.
+    ///
+    /// This is a lock-free check, suitable for refusing new work early.
+    pub fn is_stopping(&self) -> bool {
+        self.spawner.shared.is_stopping()
+    }
+
+    /// Whether observing the pool ran to completion, successfully or not.
+    pub fn is_finished(&self) -> bool {
+        self.spawner.shared.is_finished()
+    }
+
+    /// Release the start barrier, letting all tasks waiting at it begin executing.
+    ///
+    /// Does nothing if there is no barrier or the pool is already stopping.
+    pub fn release(&self) -> &Self {
+        self.spawner.shared.release();
+        self
+    }
+
+    /// Register a future without executing it yet.
+    ///
+    /// All added futures are launched together by `start()` (or `run()`), which avoids tasks failing while the others are still being set up.
+    pub fn add<Fut>(&mut self, future: Fut) -> &mut Self
+    where
+        Fut: Future<Output = Result<(), PoolError>> + Send + 'static,
+    {
+        self.added.get_mut().unwrap().push(future.boxed());
+        self
+    }
+
+    /// Launch all futures registered with `add()` at once.
+    ///
+    /// Every task is registered with the pool before the first one starts executing.
+    pub fn start(&mut self) -> &mut Self {
+        let added = std::mem::take(self.added.get_mut().unwrap());
+        let registrations: Vec<_> = added
+            .into_iter()
+            .map(|future| (self.spawner.register(None), future))
+            .collect();
+        for ((id, registration), future) in registrations {
+            if let Some((stopped, state)) = registration {
+                self.spawner
+                    .launch(id, stopped, state, future, TaskOptions::default());
             }
         }
-        Ok(())
+        self
     }
 
-    /// Stop the execution of all spawned tasks.
-    pub async fn stop(&self, why: PoolError) {
-        self.control_sender
-            .send(Err(why))
-            .await
-            .expect(INTERNAL_CHANNEL)
+    /// The id of the task whose error stopped the pool, if any.
+    pub fn failed_task(&self) -> Option<TaskId> {
+        *self.spawner.shared.failed_task.lock().unwrap()
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use futures::{executor::block_on, executor::ThreadPool, join};
+    /// Check whether a task failed without consuming the pool.
+    ///
+    /// Completions already reported by the tasks are inspected without waiting, and remain buffered for a later `observe()`.
+    /// Returns a clone of the first pending task error, if any. Panicked tasks are only reported by `observe()`.
+    /// While another `observe()` is waiting on the pool, that observer consumes the completions instead and this returns `None`.
+    pub fn peek_error(&self) -> Option<PoolError>
+    where
+        PoolError: Clone,
+    {
+        self.spawner.shared.peek_error()
+    }
 
-    use crate::StoppableThreadPool;
+    /// The context recorded for the task `id`, if any.
+    pub fn task_context(&self, id: TaskId) -> Option<String> {
+        self.spawner
+            .shared
+            .tasks
+            .lock()
+            .unwrap()
+            .get(id.0)?
+            .context
+            .clone()
+    }
 
-    async fn ok() -> Result<(), String> {
-        Ok(())
+    /// How long the task `id` ran, from its first poll until it completed or was cancelled.
+    ///
+    /// `None` while the task is running, or if it was cancelled before it was ever polled.
+    pub fn task_duration(&self, id: TaskId) -> Option<Duration> {
+        self.spawner.shared.task_duration(id)
     }
 
-    async fn forever() -> Result<(), String> {
-        loop {}
+    /// How often the task `id` was polled so far, `None` unless it was spawned with `spawn_with_poll_limit()`.
+    pub fn task_polls(&self, id: TaskId) -> Option<usize> {
+        let tasks = self.spawner.shared.tasks.lock().unwrap();
+        let polls = tasks.get(id.0)?.polls.as_ref()?;
+        Some(polls.load(Ordering::Acquire))
     }
 
-    async fn fail(msg: String) -> Result<(), String> {
-        Err(msg)
+    /// Aggregated run times of all tasks which finished running so far, `None` if there are none.
+    ///
+    /// Cancelled tasks are included with the time they ran until the stop signal reached them, so this is best queried once they all exited.
+    pub fn durations(&self) -> Option<TaskDurations> {
+        let tasks = self.spawner.shared.tasks.lock().unwrap();
+        TaskDurations::from_durations(tasks.iter().filter_map(|task| task.duration))
     }
 
-    #[test]
-    fn observe_ok() {
-        let mut pool = StoppableThreadPool::new().unwrap();
-        for _ in 0..1000 {
-            pool.spawn(ok());
+    /// Create a handle that can observe the pool from elsewhere, see `PoolObserver`.
+    pub fn observer(&self) -> PoolObserver<PoolError, StopReason> {
+        PoolObserver {
+            shared: self.spawner.shared.clone(),
         }
+    }
 
-        block_on(async { assert_eq!(pool.observe().await.unwrap(), (),) });
+    /// The warnings reported by the tasks so far, in the order they were reported.
+    ///
+    /// Warnings are kept regardless of how the pool ends, so a failed run still reports the ones gathered before the failure.
+    pub fn warnings(&self) -> Vec<Warning> {
+        self.spawner.shared.warnings.lock().unwrap().clone()
     }
 
-    #[test]
-    fn observe_err() {
-        let mut pool = StoppableThreadPool::new().unwrap();
-        let err = "fail_function_called".to_string();
-        pool.spawn(fail(err.clone()));
-        pool.spawn(forever());
+    /// Watch how many tasks finished so far, see `ProgressWatch`.
+    ///
+    /// The counters are updated by the tasks themselves as they finish, including the ones cancelled by a stop cascade.
+    pub fn progress(&self) -> ProgressWatch {
+        ProgressWatch::new(self.spawner.shared.progress.clone())
+    }
 
-        block_on(async { assert_eq!(pool.observe().await.unwrap_err(), err) });
+    /// Create a handle to the pool which does not keep its internal state alive, see `WeakPoolHandle`.
+    pub fn downgrade(&self) -> WeakPoolHandle<PoolError, StopReason> {
+        WeakPoolHandle {
+            shared: Arc::downgrade(&self.spawner.shared),
+        }
     }
 
-    #[test]
-    fn user_stopped() {
-        let mut pool = StoppableThreadPool::new().unwrap();
-        pool.spawn(forever()).spawn(forever());
-        let stop_reason = "stopped by user".to_string();
+    /// Like `observe()`, but on failure report the cause along with the tasks that were cancelled by the stop broadcast.
+    ///
+    /// A task counts as cancelled if it had not completed at the moment the stop signal was sent to it.
+    pub async fn observe_detailed(&self) -> Result<(), Failure<PoolError, StopReason>> {
+        self.spawner.shared.observe().await
+    }
 
-        block_on(async {
-            join!(
-                async { assert_eq!(pool.observe().await.unwrap_err(), stop_reason.clone()) },
-                pool.stop(stop_reason.clone())
-            )
-        });
+    /// Stop the execution of all spawned tasks.
+    ///
+    /// Futures spawned after calling this are never polled.
+    pub async fn stop(&self, why: StopReason) {
+        self.spawner.shared.stopping.store(true, Ordering::Release);
+        self.spawner
+            .control_sender
+            .send(Message::Stop(why))
+            .await
+            .expect(INTERNAL_CHANNEL)
     }
 
-    #[test]
-    fn change_pool() {
-        let mut pool = StoppableThreadPool::new().unwrap();
-        pool.spawn(forever());
-        pool.with_pool(ThreadPool::new().unwrap());
-        pool.spawn(fail("fail function called".to_string()));
+    /// Cancel all tasks because their work is no longer needed, `observe()` then returns `Ok(())`.
+    ///
+    /// If a task failed before `observe()` acts upon this request, the failure is reported instead.
+    pub async fn stop_ok(&self) {
+        self.spawner.shared.stopping.store(true, Ordering::Release);
+        self.spawner
+            .control_sender
+            .send(Message::StopOk)
+            .await
+            .expect(INTERNAL_CHANNEL)
+    }
+}
 
-        block_on(async {
-            assert_eq!(
-                pool.observe().await.unwrap_err(),
-                "fail function called".to_string(),
-            )
-        })
+/// The methods which report a failure as a plain `PoolError`, converting the stop reason if the pool was stopped by the user.
+impl<PoolError, StopReason> StoppableThreadPool<PoolError, StopReason>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Into<PoolError> + Send + Sync + 'static,
+{
+    /// Ensure that all spawned tasks are canceled on individual task error or any ` stop()` request issued by the user.
+    /// Call this function once all tasks are spawned.
+    /// A task that fails before a call to `observe()` is being awaited will still trigger a stop as soon as you actually start awaiting here.
+    ///
+    /// A panicking task stops the pool as well, its panic is then resumed here.
+    pub async fn observe(&self) -> Result<(), PoolError> {
+        plain_result(self.observe_detailed().await)
+    }
+
+    /// Stop accepting new tasks and wait for the ones already spawned to complete.
+    ///
+    /// Unlike `stop()` nothing is cancelled, unless a task fails while draining and stops the pool as usual.
+    /// Futures spawned after calling this are never polled and count as cancelled.
+    pub async fn drain(&self) -> Result<(), PoolError> {
+        {
+            // Taking the lock orders this with concurrent registrations.
+            let _tasks = self.spawner.shared.tasks.lock().unwrap();
+            self.spawner.shared.sealed.store(true, Ordering::Release);
+        }
+        self.observe().await
+    }
+
+    /// Launch every future from `tasks` together with the ones registered by `add()` and observe the pool, consuming it.
+    ///
+    /// Since the pool is consumed, no futures can be spawned after observing started.
+    pub async fn run<I, Fut>(mut self, tasks: I) -> Result<(), PoolError>
+    where
+        I: IntoIterator<Item = Fut>,
+        Fut: Future<Output = Result<(), PoolError>> + Send + 'static,
+    {
+        for future in tasks {
+            self.add(future);
+        }
+        self.start();
+        self.await
+    }
+
+    /// Observe two pools as one unit.
+    ///
+    /// Both pools are observed concurrently. If either of them fails (because one of its tasks returned an error or `stop()` was called on it), the tasks of the other pool are asked to stop as well and the error is returned.
+    /// `Ok(())` is only returned once both pools completed successfully.
+    ///
+    /// If both pools fail nearly simultaneously, the error that is received first wins and the other one is discarded.
+    /// Observing the pool that was stopped because of its partner afterwards fails with `Cause::StoppedByPartner`, carrying a copy of the error.
+    pub async fn observe_both(a: &Self, b: &Self) -> Result<(), PoolError>
+    where
+        PoolError: Clone,
+    {
+        let first = a.observe().fuse();
+        let second = b.observe().fuse();
+        pin_mut!(first, second);
+        select! {
+            output = first => match output {
+                Ok(()) => second.await,
+                Err(why) => {
+                    b.stop_by_partner(why.clone());
+                    Err(why)
+                }
+            },
+            output = second => match output {
+                Ok(()) => first.await,
+                Err(why) => {
+                    a.stop_by_partner(why.clone());
+                    Err(why)
+                }
+            }
+        }
+    }
+
+    /// Stop the tasks because the partner pool of `observe_both()` failed with `error`, the next observation reports it.
+    fn stop_by_partner(&self, error: PoolError) {
+        // Queued before the broadcast, so the cancelled tasks can't make the pool look successful.
+        let _ = self
+            .spawner
+            .control_sender
+            .try_send(Message::PartnerFailed(error));
+        let shared = &self.spawner.shared;
+        shared.announce_stop(CauseKind::StoppedByPartner);
+        shared.broadcast_stop();
+    }
+}
+
+impl<PoolError, StopReason> fmt::Debug for StoppableThreadPool<PoolError, StopReason>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Send + Sync + 'static,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StoppableThreadPool")
+            .field("name", &self.spawner.shared.name)
+            .field("execution_mode", &self.spawner.executor.mode())
+            .field(
+                "stopping",
+                &self.spawner.shared.stopping.load(Ordering::Acquire),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+/// Dropping the pool abandons all tasks that are still running: they stop executing without reporting back.
+/// Any `PoolObserver` still observing the pool resolves with `Cause::PoolDropped`.
+impl<PoolError, StopReason> Drop for StoppableThreadPool<PoolError, StopReason>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Send + Sync + 'static,
+{
+    fn drop(&mut self) {
+        if let Some(report) = self.spawner.shared.leak_report() {
+            eprintln!("{}", report);
+        }
+        let tasks = self.spawner.shared.tasks.lock().unwrap();
+        self.spawner.shared.stopping.store(true, Ordering::Release);
+        for task in tasks.iter() {
+            task.stop.close();
+        }
+        // Task handles may still hold senders, close the channel for them too.
+        self.spawner.control_sender.close();
+        self.spawner.shared.subscribers.close();
+        self.spawner.shared.bridges.close();
+    }
+}
+
+impl<PoolError, StopReason> Shared<PoolError, StopReason> {
+    fn task_spawned(&self, task: TaskId, context: Option<&String>) {
+        self.progress.spawned();
+        #[cfg(feature = "metrics")]
+        self.metrics().spawned();
+        self.subscribers.send(|| PoolEvent::TaskSpawned {
+            task,
+            context: context.cloned(),
+        });
+    }
+
+    fn task_finished(&self, task: TaskId, outcome: &TaskOutcome<PoolError>) {
+        self.progress.finished(outcome);
+        #[cfg(feature = "metrics")]
+        self.metrics().finished(outcome.into(), self.clock.now());
+        self.subscribers.send(|| PoolEvent::TaskCompleted {
+            task,
+            outcome: outcome.into(),
+        });
+        self.bridges.send(|| {
+            let context = self.tasks.lock().unwrap()[task.0].context.clone();
+            TaskOutcomeMessage::new(task, context, outcome.into())
+        });
+    }
+
+    /// The metrics handles, registered with the name of the pool on first use.
+    #[cfg(feature = "metrics")]
+    fn metrics(&self) -> &telemetry::PoolMetrics {
+        self.metrics
+            .get_or_init(|| telemetry::PoolMetrics::new(self.name.as_deref()))
+    }
+
+    /// Capture a backtrace for the failure of the task `id`, unless backtraces are disabled through the environment.
+    #[cfg(feature = "backtrace")]
+    fn capture_backtrace(&self, id: TaskId) {
+        use std::backtrace::{Backtrace, BacktraceStatus};
+
+        let backtrace = Backtrace::capture();
+        if backtrace.status() == BacktraceStatus::Captured {
+            self.tasks.lock().unwrap()[id.0].backtrace = Some(Box::new(backtrace));
+        }
+    }
+
+    fn is_stopping(&self) -> bool {
+        self.stopping.load(Ordering::Acquire)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.observed.load(Ordering::Acquire)
+    }
+
+    fn release(&self) {
+        let mut barrier = self.start_barrier.lock().unwrap();
+        if self.stopping.load(Ordering::Acquire) {
+            return;
+        }
+        if let Some((tx, _)) = barrier.take() {
+            tx.close();
+        }
+    }
+
+    /// Describes the orphaned tasks if leak detection is enabled and the pool was never observed.
+    fn leak_report(&self) -> Option<String> {
+        let enabled = self.detect_leaks.load(Ordering::Relaxed);
+        if !enabled || self.observed.load(Ordering::Acquire) {
+            return None;
+        }
+        let tasks = self.tasks.lock().unwrap();
+        if tasks.is_empty() {
+            return None;
+        }
+        let named: Vec<String> = tasks
+            .iter()
+            .enumerate()
+            .filter_map(|(id, task)| Some(format!("{} ({})", TaskId(id), task.context.as_ref()?)))
+            .collect();
+        let pool = match &self.name {
+            Some(name) => format!("pool {}", name),
+            None => "pool".to_string(),
+        };
+        let mut report = format!(
+            "poolparty: {} dropped without being observed, {} tasks orphaned",
+            pool,
+            tasks.len()
+        );
+        if !named.is_empty() {
+            report.push_str(": ");
+            report.push_str(&named.join(", "));
+        }
+        Some(report)
+    }
+
+    fn task_duration(&self, id: TaskId) -> Option<Duration> {
+        self.tasks.lock().unwrap().get(id.0)?.duration
+    }
+
+    async fn observe(&self) -> Result<(), Failure<PoolError, StopReason>> {
+        let result = self.observe_until_done().await;
+        self.finish(match &result {
+            Ok(()) => Ok(()),
+            Err(failure) => Err((&failure.cause).into()),
+        })
+        .await;
+        result
+    }
+
+    /// Run the finalizers and end the subscriptions and bridges once observing the pool finished with `result`.
+    async fn finish(&self, result: Result<(), CauseKind>) {
+        let finalizers = std::mem::take(&mut *self.finalizers.lock().unwrap());
+        for finalizer in finalizers {
+            if let Err(error) = finalizer().await {
+                self.shutdown_errors.lock().unwrap().push(error);
+            }
+        }
+        self.observed.store(true, Ordering::Release);
+        self.subscribers.send(|| PoolEvent::PoolFinished { result });
+        self.subscribers.close();
+        self.bridges.close();
+    }
+
+    async fn observe_until_done(&self) -> Result<(), Failure<PoolError, StopReason>> {
+        self.release();
+        // A stop request can still be queued although no task is left, see `stop_graceful()`.
+        let queued = !self.pending.lock().unwrap().is_empty() || !self.control_receiver.is_empty();
+        if self.outstanding.load(Ordering::Acquire) == 0 && !queued {
+            return Ok(());
+        }
+        while let Some(message) = self.next_message().await {
+            if let Message::Completed(..) = message {
+                self.outstanding.fetch_sub(1, Ordering::AcqRel);
+            }
+            let cause = match message {
+                Message::Completed(task, TaskOutcome::Failed(error)) => {
+                    *self.failed_task.lock().unwrap() = Some(task);
+                    Cause::TaskFailed { task, error }
+                }
+                Message::Completed(task, TaskOutcome::Panicked(message)) => {
+                    *self.failed_task.lock().unwrap() = Some(task);
+                    Cause::TaskPanicked { task, message }
+                }
+                Message::Completed(..) | Message::Idle => {
+                    if let Message::Idle = message {
+                        self.idle_queued.store(false, Ordering::SeqCst);
+                    }
+                    // Idle messages can be stale if tasks were respawned since.
+                    if self.outstanding.load(Ordering::SeqCst) == 0 {
+                        self.unlink();
+                        return Ok(());
+                    }
+                    continue;
+                }
+                Message::Stop(why) => Cause::Stopped(why),
+                Message::PartnerFailed(error) => Cause::StoppedByPartner { error },
+                Message::StopByTask(task, Some(why)) => Cause::StoppedByTask { task, why },
+                message @ (Message::StopOk | Message::StopByTask(_, None)) => {
+                    // A failure which raced with the request is reported instead, before any task is cancelled.
+                    if let Some(failure) = self.take_queued_failure() {
+                        self.pending.lock().unwrap().push_front(failure);
+                        continue;
+                    }
+                    let task = match message {
+                        Message::StopByTask(task, _) => Some(task),
+                        _ => None,
+                    };
+                    self.stop_ok(task);
+                    return Ok(());
+                }
+            };
+            let context = match &cause {
+                Cause::TaskFailed { task, .. }
+                | Cause::TaskPanicked { task, .. }
+                | Cause::StoppedByTask { task, .. } => {
+                    self.tasks.lock().unwrap()[task.0].context.clone()
+                }
+                _ => None,
+            };
+            self.stopping.store(true, Ordering::Release);
+            #[cfg(feature = "metrics")]
+            self.metrics().stop_requested();
+            self.announce_stop((&cause).into());
+            for (_, hook) in self.stop_hooks.lock().unwrap().iter() {
+                hook(&cause);
+            }
+            let cancelled = self.broadcast_stop();
+            #[cfg(feature = "backtrace")]
+            let backtrace = match &cause {
+                Cause::TaskFailed { task, .. } | Cause::TaskPanicked { task, .. } => {
+                    self.tasks.lock().unwrap()[task.0].backtrace.take()
+                }
+                _ => None,
+            };
+            return Err(Failure {
+                cause,
+                context,
+                cancelled,
+                pool: self.name.clone(),
+                draining: self.draining.load(Ordering::Acquire),
+                #[cfg(feature = "backtrace")]
+                backtrace,
+            });
+        }
+        let cause = Cause::PoolDropped;
+        for (_, hook) in self.stop_hooks.lock().unwrap().iter() {
+            hook(&cause);
+        }
+        Err(Failure {
+            cause,
+            context: None,
+            cancelled: Vec::new(),
+            pool: self.name.clone(),
+            draining: self.draining.load(Ordering::Acquire),
+            #[cfg(feature = "backtrace")]
+            backtrace: None,
+        })
+    }
+
+    /// Report the outcome of a task which finished after it was sent the stop signal, collecting it if enabled.
+    fn report_late(
+        &self,
+        control: &Sender<Message<PoolError, StopReason>>,
+        id: TaskId,
+        outcome: TaskOutcome<PoolError>,
+    ) {
+        {
+            let mut late = self.late_completions.lock().unwrap();
+            match &mut *late {
+                Some(late) if !matches!(outcome, TaskOutcome::Cancelled) => {
+                    late.push((id, outcome));
+                }
+                _ => {
+                    drop(late);
+                    return self.report(control, id, outcome);
+                }
+            }
+        }
+        self.finish_outstanding(control)
+    }
+
+    /// Report the outcome of a task, taking the fast path unless the task failed.
+    fn report(
+        &self,
+        control: &Sender<Message<PoolError, StopReason>>,
+        id: TaskId,
+        outcome: TaskOutcome<PoolError>,
+    ) {
+        let (outcome, ignored) = self.intercept(outcome);
+        self.report_intercepted(control, id, outcome, ignored)
+    }
+
+    /// Apply the error interceptor to the outcome of a task, returning the outcome to report and whether its error is ignored.
+    ///
+    /// A fatal outcome marks the pool as stopping right away, so the hooks of the task, which run before it is reported, already see it.
+    fn intercept(&self, outcome: TaskOutcome<PoolError>) -> (TaskOutcome<PoolError>, bool) {
+        let outcome = match outcome {
+            TaskOutcome::Failed(error) => {
+                let interceptor = self.interceptor.lock().unwrap().clone();
+                match interceptor.map(|interceptor| interceptor(&error)) {
+                    None | Some(ErrorDecision::StopPool) => TaskOutcome::Failed(error),
+                    Some(ErrorDecision::Transform(error)) => TaskOutcome::Failed(error),
+                    Some(ErrorDecision::Ignore) => return (TaskOutcome::Failed(error), true),
+                }
+            }
+            outcome => outcome,
+        };
+        if outcome.is_fatal() && !self.tolerant.load(Ordering::Acquire) {
+            // Let `is_stopping()` tell right away, rather than once the failure was observed.
+            self.stopping.store(true, Ordering::Release);
+        }
+        (outcome, false)
+    }
+
+    /// Report an outcome returned by `intercept()`.
+    fn report_intercepted(
+        &self,
+        control: &Sender<Message<PoolError, StopReason>>,
+        id: TaskId,
+        outcome: TaskOutcome<PoolError>,
+        ignored: bool,
+    ) {
+        match outcome {
+            TaskOutcome::Failed(error) if ignored => {
+                self.ignored_errors.lock().unwrap().push((id, error));
+                self.ignored.fetch_add(1, Ordering::AcqRel);
+                self.finish_outstanding(control);
+            }
+            // The control channel is unbounded, so this never fails while the pool is alive.
+            outcome if outcome.is_fatal() => {
+                let _ = control.try_send(Message::Completed(id, outcome));
+            }
+            _ => self.finish_outstanding(control),
+        }
+    }
+
+    /// Count something `observe()` waited for as done.
+    fn finish_outstanding(&self, control: &Sender<Message<PoolError, StopReason>>) {
+        if self.outstanding.fetch_sub(1, Ordering::SeqCst) == 1
+            && !self.idle_queued.swap(true, Ordering::SeqCst)
+        {
+            let _ = control.try_send(Message::Idle);
+        }
+    }
+
+    fn add_stop_hook(&self, hook: StopHook<PoolError, StopReason>) -> usize {
+        let id = self.next_hook.fetch_add(1, Ordering::Relaxed);
+        self.stop_hooks.lock().unwrap().push((id, hook));
+        id
+    }
+
+    fn peek_error(&self) -> Option<PoolError>
+    where
+        PoolError: Clone,
+    {
+        let mut pending = self.pending.lock().unwrap();
+        if self.observing.load(Ordering::SeqCst) == 0 {
+            while let Ok(message) = self.control_receiver.try_recv() {
+                pending.push_back(message);
+            }
+        }
+        pending.iter().find_map(|message| match message {
+            Message::Completed(_, TaskOutcome::Failed(error)) => Some(error.clone()),
+            _ => None,
+        })
+    }
+
+    /// Take the first failure off the control channel, keeping the other messages queued.
+    fn take_queued_failure(&self) -> Option<Message<PoolError, StopReason>> {
+        let mut pending = self.pending.lock().unwrap();
+        while let Ok(message) = self.control_receiver.try_recv() {
+            pending.push_back(message);
+        }
+        let position = pending
+            .iter()
+            .position(|message| matches!(message, Message::Completed(..)))?;
+        pending.remove(position)
+    }
+
+    /// The next message for `observe()`, preferring the ones buffered by `peek_error()`.
+    /// `None` if the control channel was closed.
+    async fn next_message(&self) -> Option<Message<PoolError, StopReason>> {
+        let _observing = Observing::new(&self.observing);
+        let buffered = self.pending.lock().unwrap().pop_front();
+        if buffered.is_some() {
+            return buffered;
+        }
+        // Drain what is available before setting up the wait.
+        if let Ok(message) = self.control_receiver.try_recv() {
+            return Some(message);
+        }
+        let received = self.control_receiver.recv().fuse();
+        let driven = self.local.drive().fuse();
+        pin_mut!(received, driven);
+        select! {
+            message = received => message.ok(),
+            () = driven => unreachable!("local tasks are driven forever"),
+        }
+    }
+
+    /// Act upon a stop which ends the observation successfully, requested by the task `task` or by the user.
+    fn stop_ok(&self, task: Option<TaskId>) {
+        #[cfg(feature = "metrics")]
+        self.metrics().stop_requested();
+        self.announce_stop(CauseKind::StoppedOk(task));
+        self.unlink();
+        self.broadcast_stop();
+    }
+
+    /// Separate the pool from all pools linked to it, in both directions, once it completed successfully.
+    fn unlink(&self) {
+        self.stop_hooks.lock().unwrap().clear();
+        // Taken out first, the other pools' hooks are removed without holding the lock.
+        let unlinks = std::mem::take(&mut *self.unlinks.lock().unwrap());
+        for unlink in unlinks {
+            unlink();
+        }
+    }
+
+    /// Publish `PoolEvent::StopRequested` unless it was published already, ahead of the broadcast so subscribers see the decision first.
+    fn announce_stop(&self, cause: CauseKind) {
+        if !self.stop_announced.swap(true, Ordering::AcqRel) {
+            self.subscribers.send(|| PoolEvent::StopRequested { cause });
+        }
+    }
+
+    /// Sends the stop signal to every running task without awaiting, so a dropped observer can't leave the pool half-cancelled.
+    fn broadcast_stop(&self) -> Vec<TaskId> {
+        let tasks = self.tasks.lock().unwrap();
+        self.stopping.store(true, Ordering::Release);
+        #[allow(unused_mut)]
+        let mut order = self.stop_order.lock().unwrap().arrange(&tasks);
+        #[cfg(feature = "metrics")]
+        self.metrics().stop_broadcast(self.clock.now());
+        #[cfg(feature = "chaos")]
+        if let Some(chaos) = &self.chaos {
+            chaos.shuffle(&mut order);
+        }
+        let mut cancelled = Vec::new();
+        for id in order {
+            let task = &tasks[id];
+            if task
+                .state
+                .compare_exchange(RUNNING, CANCELLED, Ordering::AcqRel, Ordering::Acquire)
+                .is_err()
+            {
+                continue;
+            }
+            cancelled.push(TaskId(id));
+            if task.stop.try_send(()).is_err() {
+                eprintln!("Task already finished")
+            }
+        }
+        self.subscribers.send(|| PoolEvent::StopBroadcastFinished {
+            cancelled: cancelled.len(),
+        });
+        cancelled
+    }
+}
+
+/// Counts a waiting `observe()` for as long as it is alive, even if the observing future is dropped.
+struct Observing<'a>(&'a AtomicUsize);
+
+impl<'a> Observing<'a> {
+    fn new(count: &'a AtomicUsize) -> Self {
+        count.fetch_add(1, Ordering::SeqCst);
+        Observing(count)
+    }
+}
+
+impl Drop for Observing<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// A clonable handle observing a `StoppableThreadPool` without borrowing it.
+///
+/// Unlike `StoppableThreadPool::observe()`, observing through this handle can outlive the pool:
+/// if the pool is dropped before all of its tasks completed, observing resolves with `Cause::PoolDropped` instead of success.
+pub struct PoolObserver<PoolError, StopReason = PoolError> {
+    shared: Arc<Shared<PoolError, StopReason>>,
+}
+
+impl<PoolError, StopReason> Clone for PoolObserver<PoolError, StopReason> {
+    fn clone(&self) -> Self {
+        PoolObserver {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<PoolError, StopReason> PoolObserver<PoolError, StopReason> {
+    /// Same as `StoppableThreadPool::is_stopping()`.
+    pub fn is_stopping(&self) -> bool {
+        self.shared.is_stopping()
+    }
+
+    /// Same as `StoppableThreadPool::is_finished()`.
+    pub fn is_finished(&self) -> bool {
+        self.shared.is_finished()
+    }
+
+    /// Same as `StoppableThreadPool::observe_detailed()`.
+    pub async fn observe_detailed(&self) -> Result<(), Failure<PoolError, StopReason>> {
+        self.shared.observe().await
+    }
+
+    /// Same as `StoppableThreadPool::downgrade()`.
+    pub fn downgrade(&self) -> WeakPoolHandle<PoolError, StopReason> {
+        WeakPoolHandle {
+            shared: Arc::downgrade(&self.shared),
+        }
+    }
+}
+
+/// A non-owning handle to a `StoppableThreadPool`, mirroring `std::sync::Weak`.
+///
+/// Stashing it away does not keep the internal channels and state of the pool alive.
+pub struct WeakPoolHandle<PoolError, StopReason = PoolError> {
+    shared: Weak<Shared<PoolError, StopReason>>,
+}
+
+impl<PoolError, StopReason> Clone for WeakPoolHandle<PoolError, StopReason> {
+    fn clone(&self) -> Self {
+        WeakPoolHandle {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<PoolError, StopReason> WeakPoolHandle<PoolError, StopReason> {
+    /// Same as `StoppableThreadPool::is_stopping()`, `true` once the pool was dropped.
+    pub fn is_stopping(&self) -> bool {
+        self.shared
+            .upgrade()
+            .is_none_or(|shared| shared.is_stopping())
+    }
+
+    /// Same as `StoppableThreadPool::is_finished()`, `true` once the pool was dropped.
+    pub fn is_finished(&self) -> bool {
+        self.shared
+            .upgrade()
+            .is_none_or(|shared| shared.is_finished())
+    }
+
+    /// Get an observer of the pool, `None` once the pool was dropped or shut down, i.e. it stopped because of a stop request or a failed task.
+    pub fn upgrade(&self) -> Option<PoolObserver<PoolError, StopReason>> {
+        let shared = self.shared.upgrade()?;
+        let dropped = shared.control_receiver.is_closed();
+        match dropped || shared.stopping.load(Ordering::Acquire) {
+            true => None,
+            false => Some(PoolObserver { shared }),
+        }
+    }
+}
+
+/// Awaiting the pool is equivalent to `observe().await`, but consumes the pool.
+///
+/// This rules out spawning additional futures after the pool was observed at compile time.
+impl<PoolError, StopReason> IntoFuture for StoppableThreadPool<PoolError, StopReason>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Into<PoolError> + Send + Sync + 'static,
+{
+    type Output = Result<(), PoolError>;
+    type IntoFuture = BoxFuture<'static, Result<(), PoolError>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        async move { self.observe().await }.boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_std::channel::unbounded;
+    use futures::{
+        executor::block_on,
+        executor::ThreadPool,
+        future::{pending, FutureExt},
+        join,
+        stream::{FuturesUnordered, StreamExt},
+    };
+
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use std::{fmt, time::Duration};
+
+    use crate::{
+        BoxError, Cause, DependencyError, ErrorDecision, ExecutionMode, RespawnError,
+        StoppableThreadPool, TaskId, TaskOutcome, TrySpawnError,
+    };
+
+    async fn ok() -> Result<(), String> {
+        Ok(())
+    }
+
+    #[allow(clippy::empty_loop)]
+    async fn forever() -> Result<(), String> {
+        loop {}
+    }
+
+    /// Unlike `forever()` this yields, so the task can be cancelled and leaves the thread to the others.
+    async fn idle() -> Result<(), String> {
+        pending().await
+    }
+
+    async fn fail(msg: String) -> Result<(), String> {
+        Err(msg)
+    }
+
+    #[test]
+    fn observe_ok() {
+        let mut pool = StoppableThreadPool::new().unwrap();
+        for _ in 0..1000 {
+            pool.spawn(ok());
+        }
+
+        block_on(async { assert_eq!(pool.observe().await.unwrap(), (),) });
+    }
+
+    #[test]
+    fn observe_err() {
+        let mut pool = StoppableThreadPool::new().unwrap();
+        let err = "fail_function_called".to_string();
+        pool.spawn(fail(err.clone()));
+        pool.spawn(forever());
+
+        block_on(async { assert_eq!(pool.observe().await.unwrap_err(), err) });
+    }
+
+    #[test]
+    fn user_stopped() {
+        let mut pool = StoppableThreadPool::new().unwrap();
+        pool.spawn(forever()).spawn(forever());
+        let stop_reason = "stopped by user".to_string();
+
+        block_on(async {
+            join!(
+                async { assert_eq!(pool.observe().await.unwrap_err(), stop_reason.clone()) },
+                pool.stop(stop_reason.clone())
+            )
+        });
+    }
+
+    #[test]
+    fn stopped_ok() {
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        pool.spawn(idle()).spawn(idle());
+        block_on(async {
+            pool.stop_ok().await;
+            assert_eq!(pool.observe().await, Ok(()));
+        });
+
+        // A failure queued behind the request still wins.
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        let (tx, rx) = unbounded::<()>();
+        pool.spawn(idle()).spawn(async move {
+            rx.recv().await.unwrap();
+            fail("fail".to_string()).await
+        });
+        block_on(async {
+            pool.stop_ok().await;
+            tx.send(()).await.unwrap();
+            while pool.spawner.shared.control_receiver.len() < 2 {
+                async_std::task::yield_now().await;
+            }
+            let failure = pool.observe_detailed().await.unwrap_err();
+            assert_eq!(failure.error(), Some(&"fail".to_string()));
+            assert_eq!(failure.cancelled(), &[TaskId(0)]);
+        });
+    }
+
+    #[test]
+    fn named_pool() {
+        let mut pool = StoppableThreadPool::<String>::named("ingest").unwrap();
+        assert_eq!(pool.name(), Some("ingest"));
+        assert!(format!("{:?}", pool).contains("\"ingest\""));
+        pool.spawn_with_context("fetch", async {
+            let thread = std::thread::current();
+            assert!(thread.name().unwrap().starts_with("ingest-"));
+            Err("fail".to_string())
+        });
+
+        let failure = block_on(pool.observe_detailed()).unwrap_err();
+        assert_eq!(failure.pool_name(), Some("ingest"));
+        assert_eq!(
+            failure.to_string(),
+            "task #0 (fetch) in pool ingest failed: fail"
+        );
+    }
+
+    #[cfg(feature = "backtrace")]
+    #[test]
+    fn failure_backtrace() {
+        use std::backtrace::{Backtrace, BacktraceStatus};
+
+        let mut pool = StoppableThreadPool::new().unwrap();
+        pool.spawn(fail("fail".to_string()));
+        let failure = block_on(pool.observe_detailed()).unwrap_err();
+        // Whether backtraces are enabled depends on the environment of the test run.
+        let enabled = Backtrace::capture().status() == BacktraceStatus::Captured;
+        assert_eq!(failure.backtrace().is_some(), enabled);
+
+        let mut pool = StoppableThreadPool::new().unwrap();
+        pool.spawn(idle());
+        block_on(pool.stop("stop".to_string()));
+        assert!(block_on(pool.observe_detailed())
+            .unwrap_err()
+            .backtrace()
+            .is_none());
+    }
+
+    #[test]
+    fn late_completions() {
+        let mut pool = StoppableThreadPool::new_with_pool(
+            ThreadPool::builder().pool_size(2).create().unwrap(),
+        );
+        pool.collect_late_completions();
+        let (started_tx, started_rx) = std::sync::mpsc::channel::<()>();
+        let (finish_tx, finish_rx) = std::sync::mpsc::channel::<()>();
+        let late = pool.spawn_with_handle(async move {
+            started_tx.send(()).unwrap();
+            // Blocks the worker thread, so the stop signal can't be noticed before the task finished.
+            finish_rx.recv().unwrap();
+            Err("late".to_string())
+        });
+        let late = late.id();
+        started_rx.recv().unwrap();
+        pool.spawn(fail("fail".to_string()));
+
+        assert_eq!(block_on(pool.observe()), Err("fail".to_string()));
+        finish_tx.send(()).unwrap();
+        block_on(pool.await_stopped(Duration::from_secs(5))).unwrap();
+        assert_eq!(
+            pool.take_late_completions(),
+            vec![(late, TaskOutcome::Failed("late".to_string()))]
+        );
+    }
+
+    #[test]
+    fn stress_completions() {
+        let threads = ThreadPool::builder().pool_size(4).create().unwrap();
+        for round in 0..20 {
+            let mut pool = StoppableThreadPool::new_with_pool(threads.clone());
+            for task in 0..2_000 {
+                match task == 1_000 + round {
+                    true => pool.spawn(fail("fail".to_string())),
+                    false => pool.spawn(async {
+                        async_std::task::yield_now().await;
+                        Ok(())
+                    }),
+                };
+            }
+            assert_eq!(block_on(pool.observe()), Err("fail".to_string()));
+        }
+
+        let mut pool = StoppableThreadPool::<String>::new_with_pool(threads);
+        for _ in 0..20_000 {
+            pool.spawn(ok());
+        }
+        assert_eq!(block_on(pool.observe()), Ok(()));
+    }
+
+    #[test]
+    fn stress_dropped_observers() {
+        let mut pool = StoppableThreadPool::new().unwrap();
+        pool.spawn(idle());
+        for _ in 0..1_000 {
+            pool.spawn(async {
+                async_std::task::yield_now().await;
+                Ok(())
+            });
+        }
+        // Observing futures dropped half way must neither lose nor duplicate completions.
+        for _ in 0..50 {
+            block_on(async {
+                futures::select! {
+                    _ = pool.observe().fuse() => unreachable!("a task runs forever"),
+                    () = async_std::task::yield_now().fuse() => (),
+                }
+            });
+        }
+        block_on(async {
+            join!(
+                async { assert_eq!(pool.observe().await, Err("stop".to_string())) },
+                pool.stop("stop".to_string())
+            )
+        });
+    }
+
+    #[test]
+    fn stress_stop_races() {
+        let threads = ThreadPool::builder().pool_size(4).create().unwrap();
+        for _ in 0..100 {
+            let mut pool = StoppableThreadPool::new_with_pool(threads.clone());
+            for _ in 0..50 {
+                pool.spawn(ok()).spawn(idle());
+            }
+            // The stop can't get lost, however it interleaves with the completions.
+            block_on(async {
+                join!(
+                    async { assert_eq!(pool.observe().await, Err("stop".to_string())) },
+                    pool.stop("stop".to_string())
+                )
+            });
+        }
+    }
+
+    #[test]
+    fn resize_pool() {
+        let mut pool = StoppableThreadPool::<String>::named("grow").unwrap();
+        assert_eq!(pool.pool_size(), None);
+        pool.spawn(idle());
+        pool.resize(3).unwrap();
+        assert_eq!(pool.pool_size(), Some(3));
+        pool.spawn(async {
+            assert!(std::thread::current().name().unwrap().starts_with("grow-"));
+            Err("resized".to_string())
+        });
+        assert_eq!(block_on(pool.observe()), Err("resized".to_string()));
+
+        pool.force_with_pool(ThreadPool::new().unwrap());
+        assert_eq!(pool.pool_size(), None);
+    }
+
+    #[test]
+    fn change_pool() {
+        let mut pool = StoppableThreadPool::new().unwrap();
+        pool.spawn(forever());
+        pool.with_pool(ThreadPool::new().unwrap()).unwrap();
+        pool.spawn(fail("fail function called".to_string()));
+
+        block_on(async {
+            assert_eq!(
+                pool.observe().await.unwrap_err(),
+                "fail function called".to_string(),
+            )
+        })
+    }
+
+    #[test]
+    fn change_pool_while_observed() {
+        let old = ThreadPool::new().unwrap();
+        let mut pool = StoppableThreadPool::new_with_pool(old.clone());
+        let alive = Arc::new(());
+        let task = alive.clone();
+        pool.spawn(async move {
+            let _alive = task;
+            pending().await
+        });
+        let observer = pool.observer();
+        let observing = std::thread::spawn(move || block_on(observer.observe_detailed()));
+        while pool.spawner.shared.observing.load(Ordering::SeqCst) == 0 {
+            std::thread::yield_now();
+        }
+
+        match pool.with_pool(ThreadPool::new().unwrap()) {
+            Err(rejected) => drop(rejected.into_pool()),
+            Ok(_) => panic!("swapped the executor while observed"),
+        }
+        pool.force_with_pool(ThreadPool::new().unwrap());
+        // The task spawned before keeps the previous executor alive.
+        drop(old);
+        pool.spawn(fail("fail".to_string()));
+
+        let failure = observing.join().unwrap().unwrap_err();
+        assert_eq!(failure.error(), Some(&"fail".to_string()));
+        assert_eq!(failure.cancelled(), &[TaskId(0)]);
+        while Arc::strong_count(&alive) > 1 {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn observe_both_ok() {
+        let mut a = StoppableThreadPool::new().unwrap();
+        let mut b = StoppableThreadPool::new().unwrap();
+        a.spawn(ok()).spawn(ok());
+        b.spawn(ok());
+
+        block_on(async {
+            assert_eq!(StoppableThreadPool::observe_both(&a, &b).await.unwrap(), ())
+        });
+    }
+
+    #[test]
+    fn observe_both_err() {
+        let mut a = StoppableThreadPool::new().unwrap();
+        let mut b = StoppableThreadPool::new().unwrap();
+        let err = "fail_function_called".to_string();
+        a.spawn(idle());
+        b.spawn(idle()).spawn(fail(err.clone()));
+
+        block_on(async {
+            assert_eq!(
+                StoppableThreadPool::observe_both(&a, &b).await.unwrap_err(),
+                err
+            );
+            // The task of `a` was stopped because of `b`.
+            let failure = a.observe_detailed().await.unwrap_err();
+            assert!(
+                matches!(failure.cause(), Cause::StoppedByPartner { error: partner } if *partner == err)
+            );
+        });
+    }
+
+    #[test]
+    fn spawn_n_replicas() {
+        let mut pool = StoppableThreadPool::new().unwrap();
+        let ids = pool.spawn_n(8, |index| async move {
+            match index {
+                5 => Err(format!("replica {} failed", index)),
+                _ => pending().await,
+            }
+        });
+        assert_eq!(ids.len(), 8);
+
+        block_on(async { assert_eq!(pool.observe().await.unwrap_err(), "replica 5 failed") });
+        let failed = pool.failed_task().unwrap();
+        assert_eq!(failed, ids[5]);
+        assert_eq!(pool.task_context(failed).as_deref(), Some("replica 5"));
+    }
+
+    #[test]
+    fn await_pool() {
+        let mut pool = StoppableThreadPool::new().unwrap();
+        pool.spawn(ok()).spawn(ok());
+        block_on(async { assert_eq!(pool.await.unwrap(), ()) });
+
+        let mut pool = StoppableThreadPool::new().unwrap();
+        let err = "fail_function_called".to_string();
+        pool.spawn(idle()).spawn(fail(err.clone()));
+        block_on(async { assert_eq!(pool.await.unwrap_err(), err) });
+    }
+
+    #[test]
+    fn observe_detailed_cancelled() {
+        let mut pool = StoppableThreadPool::new().unwrap();
+        let ids = pool.spawn_n(4, |index| async move {
+            match index {
+                0 => Ok(()),
+                1 => Err("replica 1 failed".to_string()),
+                _ => pending().await,
+            }
+        });
+
+        block_on(async {
+            let failure = pool.observe_detailed().await.unwrap_err();
+            match failure.cause() {
+                Cause::TaskFailed { task, error } => {
+                    assert_eq!(*task, ids[1]);
+                    assert_eq!(error, "replica 1 failed");
+                }
+                _ => panic!("expected a task failure"),
+            }
+            let cancelled = failure.cancelled();
+            assert!(cancelled.contains(&ids[2]) && cancelled.contains(&ids[3]));
+            assert!(!cancelled.contains(&ids[1]));
+        });
+    }
+
+    #[test]
+    fn pool_dropped_while_observing() {
+        let mut pool = StoppableThreadPool::new().unwrap();
+        pool.spawn(idle()).spawn(idle());
+        let observer = pool.observer();
+
+        block_on(async {
+            let (outcome, ()) = join!(observer.observe_detailed(), async { drop(pool) });
+            assert!(matches!(outcome.unwrap_err().cause(), Cause::PoolDropped));
+        });
+    }
+
+    #[test]
+    fn spawn_after_stop() {
+        let mut pool = StoppableThreadPool::new().unwrap();
+        pool.spawn(idle());
+        let stop_reason = "stopped by user".to_string();
+
+        block_on(async {
+            join!(
+                async { assert_eq!(pool.observe().await.unwrap_err(), stop_reason) },
+                pool.stop(stop_reason.clone())
+            )
+        });
+
+        let (tx, rx) = unbounded::<()>();
+        pool.spawn(async move {
+            tx.send(()).await.unwrap();
+            Ok(())
+        });
+        // The future was dropped without being polled.
+        block_on(async { assert!(rx.recv().await.is_err()) });
+    }
+
+    #[test]
+    fn spawn_racing_with_stop() {
+        let mut pool = StoppableThreadPool::new().unwrap();
+        let before: Vec<_> = (0..10).map(|_| pool.spawn_task(idle(), None)).collect();
+        block_on(pool.stop("stopped by user".to_string()));
+        let after: Vec<_> = (0..10).map(|_| pool.spawn_task(idle(), None)).collect();
+
+        block_on(async {
+            let failure = pool.observe_detailed().await.unwrap_err();
+            assert_eq!(failure.cancelled(), &before[..]);
+            assert!(after.iter().all(|id| !failure.cancelled().contains(id)));
+        });
+    }
+
+    #[test]
+    fn run_tasks() {
+        let pool = StoppableThreadPool::new().unwrap();
+        block_on(async { assert_eq!(pool.run((0..100).map(|_| ok())).await.unwrap(), ()) });
+
+        let pool = StoppableThreadPool::new().unwrap();
+        block_on(async {
+            assert_eq!(
+                pool.run(Vec::<_>::new().into_iter().map(|()| ok()))
+                    .await
+                    .unwrap(),
+                ()
+            )
+        });
+
+        let err = "fail_function_called".to_string();
+        let tasks = vec![idle().boxed(), fail(err.clone()).boxed()];
+        block_on(async {
+            assert_eq!(
+                StoppableThreadPool::run_with_pool(ThreadPool::new().unwrap(), tasks)
+                    .await
+                    .unwrap_err(),
+                err
+            )
+        });
+    }
+
+    #[test]
+    fn add_then_start() {
+        let mut pool = StoppableThreadPool::new().unwrap();
+        let err = "fail_function_called".to_string();
+        pool.add(fail(err.clone()));
+        for _ in 0..100 {
+            pool.add(idle());
+        }
+        pool.start();
+
+        block_on(async {
+            let failure = pool.observe_detailed().await.unwrap_err();
+            assert_eq!(failure.error(), Some(&err));
+            // All tasks were registered before the failing one was executed.
+            assert_eq!(failure.cancelled().len(), 100);
+        });
+    }
+
+    #[test]
+    fn start_barrier_release() {
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        let (tx, rx) = unbounded::<usize>();
+        pool.with_start_barrier();
+        for i in 0..10 {
+            let tx = tx.clone();
+            pool.spawn(async move {
+                tx.send(i).await.unwrap();
+                Ok(())
+            });
+        }
+        assert!(rx.try_recv().is_err());
+
+        pool.release();
+        block_on(async {
+            assert_eq!(pool.observe().await.unwrap(), ());
+            assert_eq!(rx.len(), 10);
+        });
+    }
+
+    #[test]
+    fn start_barrier_stopped() {
+        let mut pool = StoppableThreadPool::new().unwrap();
+        let (tx, rx) = unbounded::<()>();
+        pool.with_start_barrier();
+        for _ in 0..10 {
+            let tx = tx.clone();
+            pool.spawn(async move {
+                tx.send(()).await.unwrap();
+                Ok(())
+            });
+        }
+        drop(tx);
+
+        block_on(async {
+            pool.stop("stopped by user".to_string()).await;
+            assert_eq!(
+                pool.observe_detailed().await.unwrap_err().cancelled().len(),
+                10
+            );
+        });
+        // No future was polled, even once the pool is gone.
+        drop(pool);
+        block_on(async { assert!(rx.recv().await.is_err()) });
+    }
+
+    #[test]
+    fn spawn_with_context() {
+        let mut pool = StoppableThreadPool::new().unwrap();
+        pool.spawn_with_context("waiting", idle())
+            .spawn_with_context("syncing shard 7", fail("disk full".to_string()));
+
+        block_on(async {
+            let failure = pool.observe_detailed().await.unwrap_err();
+            assert_eq!(failure.context(), Some("syncing shard 7"));
+            assert_eq!(
+                failure.to_string(),
+                "task #1 (syncing shard 7) failed: disk full"
+            );
+        });
+    }
+
+    #[test]
+    fn respawn_task() {
+        let mut pool = StoppableThreadPool::new().unwrap();
+        let runs = Arc::new(AtomicUsize::new(0));
+        let (tx, rx) = unbounded::<()>();
+        let handle = pool.spawn_factory({
+            let runs = runs.clone();
+            move || {
+                let (runs, rx) = (runs.clone(), rx.clone());
+                async move {
+                    rx.recv().await.unwrap();
+                    runs.fetch_add(1, Ordering::SeqCst);
+                    Ok(())
+                }
+            }
+        });
+        assert_eq!(handle.respawn(), Err(RespawnError::Running));
+
+        block_on(async {
+            tx.send(()).await.unwrap();
+            while handle.respawn() == Err(RespawnError::Running) {
+                std::thread::yield_now();
+            }
+            tx.send(()).await.unwrap();
+            // Both runs are accounted for.
+            assert_eq!(pool.observe().await.unwrap(), ());
+            assert_eq!(runs.load(Ordering::SeqCst), 2);
+
+            pool.stop("stopped by user".to_string()).await;
+            assert_eq!(handle.respawn(), Err(RespawnError::Stopping));
+        });
+    }
+
+    #[test]
+    fn downcast_failure() {
+        #[derive(Debug, PartialEq)]
+        struct ShardError(usize);
+
+        impl fmt::Display for ShardError {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "shard {} failed", self.0)
+            }
+        }
+
+        impl std::error::Error for ShardError {}
+
+        let mut pool = StoppableThreadPool::<BoxError>::new().unwrap();
+        pool.spawn(async { Err(ShardError(7).into()) })
+            .spawn(async {
+                pending::<()>().await;
+                Ok(())
+            });
+
+        block_on(async {
+            let failure = pool.observe_detailed().await.unwrap_err();
+            assert!(failure.is::<ShardError>());
+            assert!(!failure.is::<std::io::Error>());
+            assert_eq!(failure.downcast_ref::<ShardError>(), Some(&ShardError(7)));
+            let failure = failure.downcast::<std::io::Error>().unwrap_err();
+            assert_eq!(failure.downcast::<ShardError>().unwrap(), ShardError(7));
+        });
+    }
+
+    #[test]
+    fn panicking_task() {
+        let mut pool = StoppableThreadPool::new().unwrap();
+        pool.spawn(idle()).spawn(async { panic!("boom") });
+
+        block_on(async {
+            let failure = pool.observe_detailed().await.unwrap_err();
+            match failure.cause() {
+                Cause::TaskPanicked { message, .. } => assert_eq!(message, "boom"),
+                _ => panic!("expected a panic"),
+            }
+            assert_eq!(failure.cancelled().len(), 1);
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "task #0 panicked: boom")]
+    fn observe_resumes_panic() {
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        pool.spawn(async { panic!("boom") });
+        block_on(pool.observe()).unwrap();
+    }
+
+    #[test]
+    fn timed_out_task() {
+        let mut pool = StoppableThreadPool::new().unwrap();
+        pool.spawn_with_timeout(Duration::from_millis(10), idle())
+            .spawn(ok());
+        block_on(async { assert_eq!(pool.observe().await.unwrap(), ()) });
+    }
+
+    #[test]
+    fn separate_stop_reason() {
+        #[derive(Debug, PartialEq)]
+        enum Shutdown {
+            UserRequest,
+        }
+
+        let mut pool = StoppableThreadPool::<String, Shutdown>::new_with_reason().unwrap();
+        pool.spawn(idle());
+
+        block_on(async {
+            pool.stop(Shutdown::UserRequest).await;
+            let failure = pool.observe_detailed().await.unwrap_err();
+            assert_eq!(failure.stop_reason(), Some(&Shutdown::UserRequest));
+            assert_eq!(failure.error(), None);
+        });
+    }
+
+    #[test]
+    fn peek_error() {
+        let mut pool = StoppableThreadPool::new().unwrap();
+        assert_eq!(pool.peek_error(), None);
+
+        pool.spawn(ok()).spawn(fail("fail".to_string())).spawn(ok());
+        while pool.peek_error().is_none() {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(pool.peek_error(), Some("fail".to_string()));
+
+        block_on(async { assert_eq!(pool.observe().await.unwrap_err(), "fail".to_string()) });
+    }
+
+    #[test]
+    fn peek_error_keeps_completions() {
+        let mut pool = StoppableThreadPool::new().unwrap();
+        pool.spawn(ok()).spawn(ok());
+        std::thread::sleep(Duration::from_millis(10));
+        assert_eq!(pool.peek_error(), None);
+
+        block_on(async { assert_eq!(pool.observe().await.unwrap(), ()) });
+    }
+
+    #[test]
+    fn cleanup_after_cancel() {
+        let cleaned = Arc::new(AtomicUsize::new(0));
+        let counter = |cleaned: &Arc<AtomicUsize>| {
+            let cleaned = cleaned.clone();
+            async move {
+                cleaned.fetch_add(1, Ordering::SeqCst);
+            }
+        };
+
+        let mut pool = StoppableThreadPool::new().unwrap();
+        pool.spawn_with_cleanup(idle(), counter(&cleaned), None)
+            .spawn_with_cleanup(ok(), counter(&cleaned), None)
+            .spawn_with_cleanup(
+                idle(),
+                async { pending::<()>().await },
+                Some(Duration::from_millis(10)),
+            )
+            .spawn(fail("fail".to_string()));
+
+        block_on(async {
+            let failure = pool.observe_detailed().await.unwrap_err();
+            assert_eq!(failure.error(), Some(&"fail".to_string()));
+        });
+        // Cleaning up happens after the stop signal was sent.
+        while cleaned.load(Ordering::SeqCst) == 0 {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        std::thread::sleep(Duration::from_millis(10));
+        assert_eq!(cleaned.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn join_handles_unordered() {
+        fn assert_bounds<T: Send + Unpin + 'static>(_: &T) {}
+
+        let mut pool = StoppableThreadPool::new().unwrap();
+        assert_bounds(&pool.spawn_with_handle(ok()));
+        let mut handles: FuturesUnordered<_> = (0..100)
+            .map(|i| match i {
+                42 => pool.spawn_with_handle(fail("fail".to_string())),
+                _ => pool.spawn_with_handle(ok()),
+            })
+            .collect();
+
+        block_on(async {
+            let mut outcomes = Vec::new();
+            while let Some(outcome) = handles.next().await {
+                outcomes.push(outcome);
+            }
+            assert_eq!(outcomes.len(), 100);
+            assert!(outcomes.contains(&TaskOutcome::Failed("fail".to_string())));
+            assert_eq!(pool.observe().await.unwrap_err(), "fail".to_string());
+        });
+    }
+
+    #[test]
+    fn task_durations() {
+        let mut pool = StoppableThreadPool::new().unwrap();
+        let slow = pool.spawn_with_handle(async {
+            async_std::task::sleep(Duration::from_millis(20)).await;
+            Ok(())
+        });
+        let slow = slow.id();
+        let cancelled = pool.spawn_with_handle(idle());
+
+        block_on(async {
+            // Durations count from the first poll, which the busy tasks of other tests can delay.
+            while !pool
+                .running_tasks()
+                .iter()
+                .any(|task| task.id() == cancelled.id())
+            {
+                async_std::task::yield_now().await;
+            }
+            async_std::task::sleep(Duration::from_millis(30)).await;
+            assert!(pool.task_duration(slow).unwrap() >= Duration::from_millis(20));
+            assert_eq!(pool.task_duration(cancelled.id()), None);
+
+            pool.stop("stop".to_string()).await;
+            assert!(pool.observe().await.is_err());
+            assert_eq!(cancelled.await, TaskOutcome::Cancelled);
+        });
+        let durations = pool.durations().unwrap();
+        assert_eq!(durations.count(), 2);
+        assert!(durations.max() >= Duration::from_millis(30));
+        assert!(durations.min() >= Duration::from_millis(20));
+    }
+
+    #[test]
+    fn local_execution() {
+        let mut pool = StoppableThreadPool::new_local();
+        assert_eq!(pool.execution_mode(), ExecutionMode::Local);
+        pool.spawn(ok()).spawn(idle());
+        let handle = pool.spawn_with_handle(async {
+            async_std::task::sleep(Duration::from_millis(10)).await;
+            fail("fail".to_string()).await
+        });
+
+        block_on(async {
+            assert_eq!(pool.observe().await.unwrap_err(), "fail".to_string());
+            assert_eq!(handle.await, TaskOutcome::Failed("fail".to_string()));
+        });
+
+        let pool = StoppableThreadPool::<String>::new_with_fallback();
+        assert_eq!(pool.execution_mode(), ExecutionMode::ThreadPool);
+    }
+
+    #[cfg(feature = "global-executor")]
+    #[test]
+    fn global_execution() {
+        let mut pool = StoppableThreadPool::new_global();
+        assert_eq!(pool.execution_mode(), ExecutionMode::Global);
+        pool.spawn(ok())
+            .spawn(idle())
+            .spawn(fail("fail".to_string()));
+
+        block_on(async { assert_eq!(pool.observe().await.unwrap_err(), "fail".to_string()) });
+    }
+
+    #[test]
+    fn weak_pool_handle() {
+        let mut pool = StoppableThreadPool::new().unwrap();
+        pool.spawn(idle());
+        let weak = pool.downgrade();
+        assert!(weak.upgrade().is_some());
+
+        block_on(async { pool.stop("stop".to_string()).await });
+        assert!(weak.upgrade().is_none());
+
+        let pool = StoppableThreadPool::<String>::new().unwrap();
+        let weak = pool.observer().downgrade();
+        drop(pool);
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[test]
+    fn progress_watch() {
+        let mut pool = StoppableThreadPool::new().unwrap();
+        let mut progress = pool.progress();
+        pool.spawn(ok()).spawn(ok()).spawn(idle());
+
+        block_on(async {
+            while progress.get().completed() < 2 {
+                progress.changed().await;
+            }
+            pool.stop("stop".to_string()).await;
+            assert!(pool.observe().await.is_err());
+            while progress.get().cancelled() < 1 {
+                progress.changed().await;
+            }
+        });
+        let progress = progress.get();
+        assert_eq!(progress.total_spawned(), 3);
+        assert_eq!(progress.finished(), 3);
+        assert_eq!(progress.failed(), 0);
+    }
+
+    #[test]
+    fn task_stops_pool() {
+        let mut pool = StoppableThreadPool::new().unwrap();
+        pool.spawn(idle());
+        pool.spawn_with_control(|control| async move {
+            control.stop_ok();
+            idle().await
+        });
+        block_on(async { assert_eq!(pool.observe().await, Ok(())) });
+
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        let (tx, rx) = unbounded();
+        let first = pool.spawn_with_control(|control| async move {
+            assert!(control.stop("found it".to_string()));
+            tx.send(()).await.unwrap();
+            Ok(())
+        });
+        pool.spawn_with_control(|control| async move {
+            rx.recv().await.unwrap();
+            assert!(!control.stop("found it too".to_string()));
+            Ok(())
+        });
+        block_on(async {
+            let failure = pool.observe_detailed().await.unwrap_err();
+            assert!(matches!(failure.cause(), Cause::StoppedByTask { task, .. } if *task == first));
+            assert_eq!(failure.stop_reason(), Some(&"found it".to_string()));
+        });
+    }
+
+    #[test]
+    fn task_dependencies() {
+        let loaded = Arc::new(AtomicUsize::new(0));
+        let mut pool = StoppableThreadPool::new().unwrap();
+        let load = {
+            let loaded = loaded.clone();
+            pool.spawn_with_handle(async move {
+                async_std::task::sleep(Duration::from_millis(10)).await;
+                loaded.store(1, Ordering::SeqCst);
+                Ok(())
+            })
+        };
+        let transform = pool
+            .spawn_after_task(vec![load.id()], async move {
+                assert_eq!(loaded.load(Ordering::SeqCst), 1);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(
+            pool.spawn_after_task(vec![TaskId(42)], ok()),
+            Err(DependencyError::UnknownTask(TaskId(42)))
+        );
+        block_on(async { assert_eq!(pool.observe().await, Ok(())) });
+        assert!(pool.task_duration(transform).is_some());
+
+        let mut pool = StoppableThreadPool::new().unwrap();
+        let (tx, rx) = unbounded();
+        let failing = pool.spawn_with_handle(fail("fail".to_string()));
+        let dependent = pool.spawn_with_handle(async move {
+            rx.recv().await.unwrap();
+            Ok(())
+        });
+        let never = pool
+            .spawn_after_task(vec![failing.id(), dependent.id()], async move {
+                tx.send(()).await.unwrap();
+                Ok(())
+            })
+            .unwrap();
+        block_on(async { assert_eq!(pool.observe().await, Err("fail".to_string())) });
+        assert!(pool.task_duration(never).is_none());
+    }
+
+    #[test]
+    fn chained_handles() {
+        let mut pool = StoppableThreadPool::new().unwrap();
+        let follow_up = pool
+            .spawn_with_handle(ok())
+            .then_spawn(|outcome| async move {
+                assert_eq!(outcome, TaskOutcome::Completed);
+                fail("follow-up".to_string()).await
+            })
+            .map_outcome(|outcome| outcome.is_fatal());
+        block_on(async {
+            assert_eq!(pool.observe().await, Err("follow-up".to_string()));
+            assert!(follow_up.await);
+        });
+
+        let mut pool = StoppableThreadPool::new().unwrap();
+        let called = Arc::new(AtomicUsize::new(0));
+        let follow_up = pool.spawn_with_handle(idle()).then_spawn({
+            let called = called.clone();
+            move |_| async move {
+                called.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+        });
+        block_on(async {
+            pool.stop("stop".to_string()).await;
+            assert!(pool.observe().await.is_err());
+            assert_eq!(follow_up.await, TaskOutcome::Cancelled);
+        });
+        assert_eq!(called.load(Ordering::SeqCst), 0);
+
+        // A failing parent stops the pool, so the follow-up never sees its outcome.
+        let mut pool = StoppableThreadPool::new().unwrap();
+        let follow_up = pool
+            .spawn_with_handle(fail("parent".to_string()))
+            .then_spawn({
+                let called = called.clone();
+                move |_| async move {
+                    called.fetch_add(1, Ordering::SeqCst);
+                    Ok(())
+                }
+            });
+        block_on(async {
+            assert_eq!(pool.observe().await, Err("parent".to_string()));
+            assert_eq!(follow_up.await, TaskOutcome::Cancelled);
+        });
+        assert_eq!(called.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn start_rate() {
+        let mut pool = StoppableThreadPool::new().unwrap();
+        pool.set_start_rate(100, 2);
+        let started = std::time::Instant::now();
+        for _ in 0..5 {
+            pool.spawn(ok());
+        }
+        block_on(async { assert_eq!(pool.observe().await, Ok(())) });
+        // Two tasks start right away, the other three one every 10 ms.
+        assert!(started.elapsed() >= Duration::from_millis(25));
+
+        let mut pool = StoppableThreadPool::new().unwrap();
+        pool.set_start_rate(1, 1).spawn(ok()).spawn(ok());
+        block_on(async {
+            pool.stop("stop".to_string()).await;
+            assert!(pool.observe().await.is_err());
+        });
+    }
+
+    #[test]
+    fn task_warnings() {
+        let mut pool = StoppableThreadPool::new().unwrap();
+        let (tx, rx) = unbounded();
+        let warned = pool.spawn_with_control(|control| async move {
+            control.warn("skipped record 7");
+            tx.send(()).await.unwrap();
+            Ok(())
+        });
+        pool.spawn_with_control(|control| async move {
+            rx.recv().await.unwrap();
+            control.warn(format!("deprecated input {}", 3));
+            fail("fail".to_string()).await
+        });
+
+        block_on(async { assert_eq!(pool.observe().await, Err("fail".to_string())) });
+        let warnings = pool.warnings();
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].task(), warned);
+        assert_eq!(warnings[0].message(), "skipped record 7");
+        assert_eq!(warnings[1].message(), "deprecated input 3");
+    }
+
+    #[test]
+    fn stop_survives_dropped_observer() {
+        let mut pool = StoppableThreadPool::new().unwrap();
+        let alive = Arc::new(());
+        for _ in 0..50 {
+            let alive = alive.clone();
+            pool.spawn(async move {
+                let _alive = alive;
+                pending().await
+            });
+        }
+        pool.spawn(fail("fail".to_string()));
+
+        block_on(async {
+            let mut observe = Box::pin(pool.observe());
+            // Poll until the failure was received, then drop the observer.
+            while futures::poll!(observe.as_mut()).is_pending() {
+                async_std::task::yield_now().await;
+            }
+        });
+        while Arc::strong_count(&alive) > 1 {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn leak_report() {
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        pool.detect_leaks()
+            .spawn(pending())
+            .spawn_with_context("upload", pending());
+        assert_eq!(
+            pool.spawner.shared.leak_report().as_deref(),
+            Some("poolparty: pool dropped without being observed, 2 tasks orphaned: task #1 (upload)")
+        );
+
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        pool.detect_leaks().spawn(ok());
+        block_on(pool.observe()).unwrap();
+        assert_eq!(pool.spawner.shared.leak_report(), None);
+    }
+
+    #[test]
+    fn drain() {
+        let mut pool = StoppableThreadPool::new().unwrap();
+        let (tx, rx) = unbounded::<()>();
+        pool.spawn(async move {
+            rx.recv().await.unwrap();
+            ok().await
+        });
+        let finish = async move {
+            async_std::task::sleep(Duration::from_millis(10)).await;
+            tx.send(()).await.unwrap();
+        };
+        block_on(async { futures::join!(pool.drain(), finish).0 }).unwrap();
+        assert_eq!(pool.progress().get().completed(), 1);
+
+        pool.spawn(fail("late".to_string()));
+        assert_eq!(block_on(pool.observe()), Ok(()));
+        assert_eq!(pool.progress().get().cancelled(), 1);
+    }
+
+    #[test]
+    fn drain_failure_stops() {
+        let mut pool = StoppableThreadPool::new().unwrap();
+        pool.spawn(pending()).spawn(fail("fail".to_string()));
+        assert_eq!(block_on(pool.drain()), Err("fail".to_string()));
+    }
+
+    #[test]
+    fn failure_not_queued_behind_completions() {
+        let mut pool = StoppableThreadPool::new().unwrap();
+        let mut progress = pool.progress();
+        pool.spawn_n(1000, |_| ok());
+        block_on(async { while progress.changed().await.finished() < 1000 {} });
+        // At most the message telling that the pool became idle.
+        assert!(pool.spawner.shared.control_receiver.len() <= 1);
+
+        pool.spawn(pending()).spawn(fail("fail".to_string()));
+        assert_eq!(block_on(pool.observe()), Err("fail".to_string()));
+    }
+
+    #[test]
+    fn try_spawn() {
+        let mut pool = StoppableThreadPool::new().unwrap();
+        pool.set_pending_limit(1);
+        assert!(pool.try_spawn(pending()).is_ok());
+        let future = match pool.try_spawn(ok()) {
+            Err(TrySpawnError::Full(future)) => future,
+            other => panic!("unexpected {:?}", other.map(|_| ())),
+        };
+
+        block_on(async {
+            pool.stop("stop".to_string()).await;
+            assert!(matches!(
+                pool.try_spawn(future),
+                Err(TrySpawnError::Stopped(_))
+            ));
+            assert_eq!(pool.observe().await, Err("stop".to_string()));
+        });
+
+        // A finished pool refuses futures without leaving an entry behind.
+        let mut pool = StoppableThreadPool::new().unwrap();
+        pool.spawn(ok());
+        assert_eq!(block_on(pool.observe()), Ok(()));
+        assert!(matches!(
+            pool.try_spawn(ok()),
+            Err(TrySpawnError::Stopped(_))
+        ));
+        assert_eq!(pool.progress().get().total_spawned(), 1);
+    }
+
+    #[test]
+    fn is_stopping() {
+        let mut pool = StoppableThreadPool::new().unwrap();
+        let weak = pool.downgrade();
+        let observer = pool.observer();
+        pool.spawn(pending()).spawn(fail("fail".to_string()));
+        while !observer.is_stopping() {
+            std::thread::yield_now();
+        }
+        assert!(pool.is_stopping() && weak.is_stopping());
+        assert!(!pool.is_finished());
+
+        assert_eq!(block_on(pool.observe()), Err("fail".to_string()));
+        assert!(pool.is_finished() && observer.is_finished());
+        drop((pool, observer));
+        assert!(weak.is_stopping() && weak.is_finished());
+    }
+
+    #[test]
+    fn on_shutdown() {
+        let mut pool = StoppableThreadPool::new().unwrap();
+        let (tx, rx) = unbounded();
+        let first = tx.clone();
+        pool.spawn(pending())
+            .spawn(fail("fail".to_string()))
+            .on_shutdown(move || async move {
+                first.send(1).await.unwrap();
+                Err("flush failed".to_string())
+            })
+            .on_shutdown(move || async move {
+                tx.send(2).await.unwrap();
+                Ok(())
+            });
+
+        assert_eq!(block_on(pool.observe()), Err("fail".to_string()));
+        assert_eq!((rx.try_recv(), rx.try_recv()), (Ok(1), Ok(2)));
+        assert_eq!(
+            pool.take_shutdown_errors(),
+            vec!["flush failed".to_string()]
+        );
+        // Finalizers only run once.
+        let _ = block_on(pool.observe());
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn error_interceptor() {
+        let mut pool = StoppableThreadPool::new().unwrap();
+        pool.set_error_interceptor(|error: &String| match error.as_str() {
+            "minor" => ErrorDecision::Ignore,
+            "io" => ErrorDecision::Transform("fatal io".to_string()),
+            _ => ErrorDecision::StopPool,
+        });
+        let minor = pool.spawn_with_control(|_| fail("minor".to_string()));
+        let (tx, rx) = unbounded::<()>();
+        pool.spawn(async move {
+            rx.recv().await.unwrap();
+            fail("io".to_string()).await
+        });
+
+        block_on(async {
+            while pool.progress().get().failed() == 0 {
+                async_std::task::yield_now().await;
+            }
+            assert!(!pool.is_stopping());
+            tx.send(()).await.unwrap();
+            assert_eq!(pool.observe().await, Err("fatal io".to_string()));
+        });
+        assert_eq!(
+            pool.take_ignored_errors(),
+            vec![(minor, "minor".to_string())]
+        );
     }
 }
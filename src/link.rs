@@ -0,0 +1,212 @@
+use std::sync::{atomic::Ordering, Arc, Weak};
+
+use crate::{Cause, Message, Shared, StoppableThreadPool};
+
+/// Called with the cause of a failure when a pool enters its stop cascade.
+pub(crate) type StopHook<PoolError, StopReason> =
+    Box<dyn Fn(&Cause<PoolError, StopReason>) + Send + Sync>;
+
+/// Removes the stop hook a linked pool holds towards the pool storing it.
+pub(crate) type Unlink = Box<dyn FnOnce() + Send + Sync>;
+
+/// The link between two pools created by `StoppableThreadPool::link()`.
+///
+/// Dropping it leaves the pools linked, call `unlink()` to separate them again.
+pub struct PoolLink {
+    unlink: Box<dyn FnOnce() + Send + Sync>,
+}
+
+impl PoolLink {
+    /// Separate the linked pools, a failure in one of them no longer stops the other.
+    pub fn unlink(self) {
+        (self.unlink)()
+    }
+}
+
+impl<PoolError, StopReason> StoppableThreadPool<PoolError, StopReason>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Send + Sync + 'static,
+{
+    /// Link the pools `a` and `b`, so that a failure in either of them stops the other one too.
+    ///
+    /// When `a` enters its stop cascade, `b` is stopped with the reason `a_to_b` maps the cause to, and vice versa.
+    /// Like any stop cascade, this happens while the failing pool is being observed.
+    /// The pools do not keep each other alive, and they are unlinked automatically once either of them completed successfully.
+    pub fn link<OtherError, OtherReason, AtoB, BtoA>(
+        a: &StoppableThreadPool<PoolError, StopReason>,
+        b: &StoppableThreadPool<OtherError, OtherReason>,
+        a_to_b: AtoB,
+        b_to_a: BtoA,
+    ) -> PoolLink
+    where
+        OtherError: Send + Sync + 'static,
+        OtherReason: Send + Sync + 'static,
+        AtoB: Fn(&Cause<PoolError, StopReason>) -> OtherReason + Send + Sync + 'static,
+        BtoA: Fn(&Cause<OtherError, OtherReason>) -> StopReason + Send + Sync + 'static,
+    {
+        let (a_shared, b_shared) = (&a.spawner.shared, &b.spawner.shared);
+        let to_b = a_shared.add_stop_hook(stop_hook(b, a_to_b));
+        let to_a = b_shared.add_stop_hook(stop_hook(a, b_to_a));
+        let (a_weak, b_weak) = (Arc::downgrade(a_shared), Arc::downgrade(b_shared));
+        // Either pool completing removes the hooks of both, the partner's one towards it included.
+        let weak = b_weak.clone();
+        a_shared
+            .unlinks
+            .lock()
+            .unwrap()
+            .push(Box::new(move || remove_stop_hook(&weak, to_a)));
+        let weak = a_weak.clone();
+        b_shared
+            .unlinks
+            .lock()
+            .unwrap()
+            .push(Box::new(move || remove_stop_hook(&weak, to_b)));
+        let (a_shared, b_shared) = (a_weak, b_weak);
+        PoolLink {
+            unlink: Box::new(move || {
+                remove_stop_hook(&a_shared, to_b);
+                remove_stop_hook(&b_shared, to_a);
+            }),
+        }
+    }
+}
+
+/// A hook stopping `target` with the reason `map` derives from the cause, unless it is stopping already.
+fn stop_hook<PoolError, StopReason, TargetError, TargetReason, Map>(
+    target: &StoppableThreadPool<TargetError, TargetReason>,
+    map: Map,
+) -> StopHook<PoolError, StopReason>
+where
+    TargetError: Send + Sync + 'static,
+    TargetReason: Send + Sync + 'static,
+    Map: Fn(&Cause<PoolError, StopReason>) -> TargetReason + Send + Sync + 'static,
+{
+    let shared = Arc::downgrade(&target.spawner.shared);
+    let control = target.spawner.control_sender.clone();
+    Box::new(move |cause| {
+        let shared = match shared.upgrade() {
+            Some(shared) => shared,
+            None => return,
+        };
+        if !shared.stopping.swap(true, Ordering::AcqRel) {
+            // Fails only if the target pool was dropped in the meantime.
+            let _ = control.try_send(Message::Stop(map(cause)));
+        }
+    })
+}
+
+fn remove_stop_hook<PoolError, StopReason>(
+    shared: &Weak<Shared<PoolError, StopReason>>,
+    id: usize,
+) {
+    if let Some(shared) = shared.upgrade() {
+        shared
+            .stop_hooks
+            .lock()
+            .unwrap()
+            .retain(|(hook, _)| *hook != id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{executor::block_on, future::pending};
+
+    use crate::{Cause, StoppableThreadPool};
+
+    #[test]
+    fn failure_stops_linked_pool() {
+        let mut readers = StoppableThreadPool::<String>::new().unwrap();
+        let mut writers = StoppableThreadPool::<u32>::new().unwrap();
+        StoppableThreadPool::link(
+            &readers,
+            &writers,
+            |cause| match cause {
+                Cause::TaskFailed { .. } => 1,
+                _ => 2,
+            },
+            |_| "writers failed".to_string(),
+        );
+        readers.spawn(async { Err("read failed".to_string()) });
+        writers.spawn(async { pending().await });
+
+        block_on(async {
+            assert_eq!(readers.observe().await, Err("read failed".to_string()));
+            assert_eq!(writers.observe().await, Err(1));
+        });
+    }
+
+    #[test]
+    fn unlinked_pools() {
+        let mut readers = StoppableThreadPool::<String>::new().unwrap();
+        let mut writers = StoppableThreadPool::<String>::new().unwrap();
+        let link = StoppableThreadPool::link(
+            &readers,
+            &writers,
+            |_| "readers failed".to_string(),
+            |_| "writers failed".to_string(),
+        );
+        link.unlink();
+        readers.spawn(async { Err("read failed".to_string()) });
+        writers.spawn(async { Ok(()) });
+
+        block_on(async {
+            assert_eq!(readers.observe().await, Err("read failed".to_string()));
+            assert_eq!(writers.observe().await, Ok(()));
+        });
+    }
+
+    #[test]
+    fn completed_pool_unlinked_both_ways() {
+        let mut readers = StoppableThreadPool::<String>::new().unwrap();
+        let mut writers = StoppableThreadPool::<String>::new().unwrap();
+        StoppableThreadPool::link(
+            &readers,
+            &writers,
+            |_| "readers failed".to_string(),
+            |_| "writers failed".to_string(),
+        );
+        let (tx, rx) = async_std::channel::bounded::<()>(1);
+        readers.spawn(async { Ok(()) });
+        writers.spawn(async move {
+            let _ = rx.recv().await;
+            Err("write failed".to_string())
+        });
+
+        block_on(async {
+            assert_eq!(readers.observe().await, Ok(()));
+            tx.send(()).await.unwrap();
+            assert_eq!(writers.observe().await, Err("write failed".to_string()));
+        });
+        // The failure of the writers no longer reaches the readers which completed before.
+        assert!(!readers.is_stopping());
+        for pool in [&readers, &writers].iter() {
+            assert!(pool.spawner.shared.stop_hooks.lock().unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn stopped_ok_pool_unlinked_both_ways() {
+        let mut readers = StoppableThreadPool::<String>::new().unwrap();
+        let mut writers = StoppableThreadPool::<String>::new().unwrap();
+        StoppableThreadPool::link(
+            &readers,
+            &writers,
+            |_| "readers failed".to_string(),
+            |_| "writers failed".to_string(),
+        );
+        readers.spawn(pending());
+        writers.spawn(pending());
+
+        block_on(async {
+            readers.stop_ok().await;
+            assert_eq!(readers.observe().await, Ok(()));
+        });
+        for pool in [&readers, &writers].iter() {
+            assert!(pool.spawner.shared.stop_hooks.lock().unwrap().is_empty());
+        }
+        writers.spawn(async { Err("write failed".to_string()) });
+        assert_eq!(block_on(writers.observe()), Err("write failed".to_string()));
+    }
+}
@@ -0,0 +1,137 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    task::{Context, Poll},
+};
+
+use futures::{
+    executor::ThreadPool,
+    future::{poll_fn, BoxFuture, Future},
+    stream::{FuturesUnordered, StreamExt},
+    task::AtomicWaker,
+};
+
+/// How a `StoppableThreadPool` executes its tasks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionMode {
+    /// Tasks run on the worker threads of a futures `ThreadPool`.
+    ThreadPool,
+    /// Tasks run on the thread observing the pool, and only make progress while the pool is being observed.
+    Local,
+    /// Tasks run on the global executor of `async-std`.
+    #[cfg(feature = "global-executor")]
+    Global,
+    /// Tasks run on the JavaScript event loop, through `wasm_bindgen_futures::spawn_local()`.
+    #[cfg(feature = "wasm")]
+    Wasm,
+}
+
+/// Where the wrapped tasks are spawned to.
+#[derive(Clone)]
+pub(crate) enum Executor {
+    ThreadPool(ThreadPool),
+    Local,
+    #[cfg(feature = "global-executor")]
+    Global,
+    #[cfg(feature = "wasm")]
+    Wasm,
+}
+
+impl Executor {
+    pub(crate) fn mode(&self) -> ExecutionMode {
+        match self {
+            Executor::ThreadPool(_) => ExecutionMode::ThreadPool,
+            Executor::Local => ExecutionMode::Local,
+            #[cfg(feature = "global-executor")]
+            Executor::Global => ExecutionMode::Global,
+            #[cfg(feature = "wasm")]
+            Executor::Wasm => ExecutionMode::Wasm,
+        }
+    }
+}
+
+/// Tasks of a pool in local execution mode, driven by whoever observes the pool.
+pub(crate) struct LocalTasks {
+    /// Spawning never touches `running`, so tasks can spawn while being driven.
+    incoming: Mutex<Vec<BoxFuture<'static, ()>>>,
+    /// Taken out while the tasks are driven, so the lock is never held while polling them.
+    running: Mutex<Option<FuturesUnordered<BoxFuture<'static, ()>>>>,
+    /// Whether someone tried to drive the tasks while they were taken out.
+    turned_away: AtomicBool,
+    waker: AtomicWaker,
+}
+
+impl Default for LocalTasks {
+    fn default() -> Self {
+        LocalTasks {
+            incoming: Mutex::default(),
+            running: Mutex::new(Some(FuturesUnordered::new())),
+            turned_away: AtomicBool::new(false),
+            waker: AtomicWaker::new(),
+        }
+    }
+}
+
+impl LocalTasks {
+    pub(crate) fn spawn(&self, future: BoxFuture<'static, ()>) {
+        self.incoming.lock().unwrap().push(future);
+        self.waker.wake();
+    }
+
+    /// Drive the local tasks, never completes.
+    pub(crate) fn drive(&self) -> impl Future<Output = ()> + '_ {
+        poll_fn(move |cx| self.poll_drive(cx))
+    }
+
+    fn poll_drive(&self, cx: &mut Context<'_>) -> Poll<()> {
+        self.waker.register(cx.waker());
+        let taken = self.running.lock().unwrap().take();
+        let mut running = match taken {
+            Some(running) => running,
+            // Another observer or a task calling back into the pool is driving the tasks right now.
+            None => {
+                self.turned_away.store(true, Ordering::SeqCst);
+                return Poll::Pending;
+            }
+        };
+        loop {
+            running.extend(self.incoming.lock().unwrap().drain(..));
+            match running.poll_next_unpin(cx) {
+                Poll::Ready(Some(())) => continue,
+                Poll::Ready(None) | Poll::Pending => {
+                    if self.incoming.lock().unwrap().is_empty() {
+                        break;
+                    }
+                }
+            }
+        }
+        *self.running.lock().unwrap() = Some(running);
+        if self.turned_away.swap(false, Ordering::SeqCst) {
+            // Hand over, the one turned away registered its waker last.
+            self.waker.wake();
+        }
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{executor::block_on, FutureExt};
+
+    use crate::StoppableThreadPool;
+
+    #[test]
+    fn task_observing_local_pool() {
+        let mut pool = StoppableThreadPool::<String>::new_local();
+        let observer = pool.observer();
+        // Drives the local tasks while being driven by them.
+        pool.spawn(async move {
+            assert!(observer.observe_detailed().now_or_never().is_none());
+            Ok(())
+        })
+        .spawn(async { Ok(()) });
+        assert_eq!(block_on(pool.observe()), Ok(()));
+    }
+}
@@ -0,0 +1,95 @@
+use crate::StoppableThreadPool;
+
+type Map<'a, From, To> = Box<dyn Fn(From) -> To + Send + Sync + 'a>;
+
+/// A view on a `StoppableThreadPool` translating between its error type and `Mapped`, created by `StoppableThreadPool::mapped()`.
+pub struct MappedPool<'a, PoolError, StopReason, Mapped>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Send + Sync + 'static,
+{
+    pool: &'a StoppableThreadPool<PoolError, StopReason>,
+    map_err: Map<'a, PoolError, Mapped>,
+    map_stop: Map<'a, Mapped, StopReason>,
+}
+
+impl<'a, PoolError, StopReason, Mapped> MappedPool<'a, PoolError, StopReason, Mapped>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Into<PoolError> + Send + Sync + 'static,
+{
+    /// Same as `StoppableThreadPool::observe()`, with the error mapped to `Mapped`.
+    pub async fn observe(&self) -> Result<(), Mapped> {
+        self.pool.observe().await.map_err(&self.map_err)
+    }
+
+    /// Same as `StoppableThreadPool::stop()`, with the reason mapped back to the stop reason of the pool.
+    pub async fn stop(&self, why: Mapped) {
+        self.pool.stop((self.map_stop)(why)).await
+    }
+}
+
+impl<PoolError, StopReason> StoppableThreadPool<PoolError, StopReason>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Into<PoolError> + Send + Sync + 'static,
+{
+    /// View the pool through the error type `Mapped`, so the conversion happens in one place instead of at every call site.
+    ///
+    /// `map_err` converts the errors `observe()` reports, `map_stop` converts the reasons passed to `stop()` back.
+    pub fn mapped<'a, Mapped, MapErr, MapStop>(
+        &'a self,
+        map_err: MapErr,
+        map_stop: MapStop,
+    ) -> MappedPool<'a, PoolError, StopReason, Mapped>
+    where
+        MapErr: Fn(PoolError) -> Mapped + Send + Sync + 'a,
+        MapStop: Fn(Mapped) -> StopReason + Send + Sync + 'a,
+    {
+        MappedPool {
+            pool: self,
+            map_err: Box::new(map_err),
+            map_stop: Box::new(map_stop),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{executor::block_on, future::pending};
+
+    use crate::StoppableThreadPool;
+
+    #[derive(Debug, PartialEq)]
+    enum LibError {
+        Internal(String),
+        Shutdown,
+    }
+
+    #[test]
+    fn mapped_errors() {
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        pool.spawn(async { Err("fail".to_string()) });
+        let mapped = pool.mapped(LibError::Internal, |_| "shutdown".to_string());
+        block_on(async {
+            assert_eq!(
+                mapped.observe().await,
+                Err(LibError::Internal("fail".to_string()))
+            )
+        });
+
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        pool.spawn(async { pending().await });
+        let mapped = pool.mapped(
+            |error| match error.as_str() {
+                "shutdown" => LibError::Shutdown,
+                _ => LibError::Internal(error),
+            },
+            |_| "shutdown".to_string(),
+        );
+        block_on(async {
+            mapped.stop(LibError::Shutdown).await;
+            assert_eq!(mapped.observe().await, Err(LibError::Shutdown));
+        });
+    }
+}
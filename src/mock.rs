@@ -0,0 +1,112 @@
+use std::sync::Mutex;
+
+use async_std::channel::{unbounded, Receiver, Sender};
+use futures::future::{BoxFuture, FutureExt};
+
+use crate::{StoppablePool, TaskId};
+
+type MockTask<PoolError> = BoxFuture<'static, Result<(), PoolError>>;
+
+/// A `StoppablePool` which records the spawned futures instead of executing them, for tests.
+///
+/// Tests take the spawned futures to drive them as they see fit, and decide how observing ends with `finish()`.
+/// Stopping the pool ends observing with the stop reason.
+pub struct MockPool<PoolError, StopReason = PoolError> {
+    tasks: Mutex<Vec<Option<MockTask<PoolError>>>>,
+    stopped: Mutex<Vec<StopReason>>,
+    finish: Sender<Result<(), PoolError>>,
+    finished: Receiver<Result<(), PoolError>>,
+}
+
+impl<PoolError, StopReason> Default for MockPool<PoolError, StopReason> {
+    fn default() -> Self {
+        let (finish, finished) = unbounded();
+        MockPool {
+            tasks: Mutex::new(Vec::new()),
+            stopped: Mutex::new(Vec::new()),
+            finish,
+            finished,
+        }
+    }
+}
+
+impl<PoolError, StopReason> MockPool<PoolError, StopReason> {
+    /// Create a new `MockPool` without any tasks.
+    pub fn new() -> Self {
+        MockPool::default()
+    }
+
+    /// The number of futures spawned so far.
+    pub fn spawned(&self) -> usize {
+        self.tasks.lock().unwrap().len()
+    }
+
+    /// Take the future spawned as `id`, `None` if there is no such task or it was taken before.
+    pub fn take_task(&self, id: TaskId) -> Option<MockTask<PoolError>> {
+        self.tasks.lock().unwrap().get_mut(id.0)?.take()
+    }
+
+    /// End observing with `result`, for the current or next call to `observe()`.
+    pub fn finish(&self, result: Result<(), PoolError>) {
+        let _ = self.finish.try_send(result);
+    }
+
+    /// The reasons the pool was stopped with so far.
+    pub fn stop_reasons(&self) -> Vec<StopReason>
+    where
+        StopReason: Clone,
+    {
+        self.stopped.lock().unwrap().clone()
+    }
+}
+
+impl<PoolError, StopReason> StoppablePool<PoolError, StopReason> for MockPool<PoolError, StopReason>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Clone + Into<PoolError> + Send + Sync + 'static,
+{
+    fn spawn_boxed(&mut self, future: BoxFuture<'static, Result<(), PoolError>>) -> TaskId {
+        let mut tasks = self.tasks.lock().unwrap();
+        tasks.push(Some(future));
+        TaskId(tasks.len() - 1)
+    }
+
+    fn observe(&self) -> BoxFuture<'_, Result<(), PoolError>> {
+        async move {
+            self.finished
+                .recv()
+                .await
+                .expect("the mock pool holds the sender")
+        }
+        .boxed()
+    }
+
+    fn stop(&self, why: StopReason) -> BoxFuture<'_, ()> {
+        self.stopped.lock().unwrap().push(why.clone());
+        self.finish(Err(why.into()));
+        async {}.boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{executor::block_on, FutureExt};
+
+    use super::MockPool;
+    use crate::StoppablePool;
+
+    #[test]
+    fn drive_mock() {
+        let mut pool = MockPool::<String>::new();
+        let task = pool.spawn_boxed(async { Err("fail".to_string()) }.boxed());
+        assert_eq!(pool.spawned(), 1);
+
+        let result = block_on(pool.take_task(task).unwrap());
+        pool.finish(result);
+        assert_eq!(block_on(pool.observe()), Err("fail".to_string()));
+
+        block_on(pool.stop("stop".to_string()));
+        assert_eq!(pool.stop_reasons(), vec!["stop".to_string()]);
+        assert_eq!(block_on(pool.observe()), Err("stop".to_string()));
+    }
+}
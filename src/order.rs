@@ -0,0 +1,77 @@
+use crate::{StoppableThreadPool, Task, TaskId};
+
+/// The order in which the stop signal is sent to the tasks, see `StoppableThreadPool::set_stop_order()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StopOrder {
+    /// The task spawned first is signalled first.
+    #[default]
+    Spawn,
+    /// The task spawned last is signalled first.
+    ReverseSpawn,
+    /// Tasks with a higher priority are signalled first, ties in spawn order. See `set_stop_priority()`.
+    Priority,
+}
+
+impl StopOrder {
+    /// The indices of `tasks` in the order they are signalled.
+    pub(crate) fn arrange(self, tasks: &[Task]) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..tasks.len()).collect();
+        match self {
+            StopOrder::Spawn => {}
+            StopOrder::ReverseSpawn => order.reverse(),
+            // The sort is stable, which keeps ties in spawn order.
+            StopOrder::Priority => order.sort_by_key(|&id| std::cmp::Reverse(tasks[id].priority)),
+        }
+        order
+    }
+}
+
+impl<PoolError, StopReason> StoppableThreadPool<PoolError, StopReason>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Send + Sync + 'static,
+{
+    /// Choose the order in which the stop signal is sent to the tasks, `StopOrder::Spawn` by default.
+    ///
+    /// The stop broadcast is sequential: each task is signalled (or skipped because it already finished) before the next one in the order.
+    /// Note that this orders the signals, not the exits. Tasks on different worker threads may react to their signal concurrently,
+    /// so a task which must be gone before another one is signalled should be stopped explicitly instead.
+    /// With the `chaos` feature, a chaotic pool shuffling the broadcast ignores the order.
+    pub fn set_stop_order(&mut self, order: StopOrder) -> &mut Self {
+        *self.spawner.shared.stop_order.lock().unwrap() = order;
+        self
+    }
+
+    /// Set the priority of the task `id` for `StopOrder::Priority`, tasks start with priority `0`.
+    pub fn set_stop_priority(&mut self, id: TaskId, priority: i32) -> &mut Self {
+        if let Some(task) = self.spawner.shared.tasks.lock().unwrap().get_mut(id.0) {
+            task.priority = priority;
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::future::pending;
+
+    use super::StopOrder;
+    use crate::{StoppableThreadPool, TaskId};
+
+    fn broadcast(order: StopOrder) -> Vec<TaskId> {
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        let ids: Vec<TaskId> = (0..3)
+            .map(|_| pool.spawn_with_handle(pending()).id())
+            .collect();
+        pool.set_stop_order(order).set_stop_priority(ids[1], 5);
+        pool.spawner.shared.broadcast_stop()
+    }
+
+    #[test]
+    fn stop_orders() {
+        let ids = |order: &[usize]| order.iter().map(|&id| TaskId(id)).collect::<Vec<_>>();
+        assert_eq!(broadcast(StopOrder::Spawn), ids(&[0, 1, 2]));
+        assert_eq!(broadcast(StopOrder::ReverseSpawn), ids(&[2, 1, 0]));
+        assert_eq!(broadcast(StopOrder::Priority), ids(&[1, 0, 2]));
+    }
+}
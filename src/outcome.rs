@@ -0,0 +1,41 @@
+use std::any::Any;
+
+/// What happened to an individual task.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TaskOutcome<PoolError> {
+    /// The future returned `Ok(())`.
+    Completed,
+    /// The future returned an error.
+    Failed(PoolError),
+    /// The task was stopped before it completed.
+    Cancelled,
+    /// The future panicked, carrying the panic message.
+    Panicked(String),
+    /// The future did not complete within its timeout, see `StoppableThreadPool::spawn_with_timeout()`.
+    TimedOut,
+}
+
+impl<PoolError> TaskOutcome<PoolError> {
+    /// Returns `true` if the outcome stops the pool, i.e. the task failed or panicked.
+    pub fn is_fatal(&self) -> bool {
+        matches!(self, TaskOutcome::Failed(_) | TaskOutcome::Panicked(_))
+    }
+
+    pub(crate) fn from_result(output: Result<(), PoolError>) -> TaskOutcome<PoolError> {
+        match output {
+            Ok(()) => TaskOutcome::Completed,
+            Err(error) => TaskOutcome::Failed(error),
+        }
+    }
+
+    pub(crate) fn from_panic(payload: Box<dyn Any + Send>) -> TaskOutcome<PoolError> {
+        let message = match payload.downcast::<String>() {
+            Ok(message) => *message,
+            Err(payload) => match payload.downcast::<&'static str>() {
+                Ok(message) => message.to_string(),
+                Err(_) => "Box<dyn Any>".to_string(),
+            },
+        };
+        TaskOutcome::Panicked(message)
+    }
+}
@@ -0,0 +1,255 @@
+use std::sync::{atomic::Ordering, Arc, Mutex};
+
+use futures::future::{BoxFuture, Future, FutureExt};
+
+use crate::{
+    poll::Polled, spawner::Spawner, Message, StoppableThreadPool, TaskId, TaskOptions, TaskOutcome,
+    INTERNAL_CHANNEL,
+};
+
+/// The control machinery of a `StoppableThreadPool`, see `StoppableThreadPool::into_parts()`.
+///
+/// This is an advanced API for embedding the pool into other runtimes.
+/// It exposes implementation details and may change in minor releases.
+pub struct PoolParts<PoolError, StopReason = PoolError>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Send + Sync + 'static,
+{
+    /// Receives what `observe()` would act upon.
+    pub control: ControlReceiver<PoolError, StopReason>,
+    /// Registers tasks and hands out their wrapped futures.
+    pub tasks: TaskWrapper<PoolError, StopReason>,
+    /// Sends the stop signal to the tasks.
+    pub stop: StopTrigger<PoolError, StopReason>,
+}
+
+/// What a `ControlReceiver` received.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ControlMessage<PoolError, StopReason = PoolError> {
+    /// The task `task` failed or panicked.
+    Failed {
+        task: TaskId,
+        outcome: TaskOutcome<PoolError>,
+    },
+    /// `stop()` was called.
+    Stop(StopReason),
+    /// `stop_ok()` was called.
+    StopOk,
+    /// The task `task` asked the pool to stop, with a reason unless the pool should succeed.
+    StopByTask {
+        task: TaskId,
+        why: Option<StopReason>,
+    },
+    /// All tasks finished without failing.
+    Idle,
+    /// `observe_both()` stopped the pool because the other pool failed with `error`.
+    PartnerFailed(PoolError),
+}
+
+/// The pool taken apart, shared by its parts until `from_parts()` takes it back.
+///
+/// Dropping the last part without that drops the pool, which shuts it down and stops its tasks.
+type Assembly<PoolError, StopReason> =
+    Arc<Mutex<Option<StoppableThreadPool<PoolError, StopReason>>>>;
+
+/// Receives the failures and stop requests of the pool's tasks.
+pub struct ControlReceiver<PoolError, StopReason = PoolError>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Send + Sync + 'static,
+{
+    spawner: Spawner<PoolError, StopReason>,
+    _pool: Assembly<PoolError, StopReason>,
+}
+
+impl<PoolError, StopReason> ControlReceiver<PoolError, StopReason>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Send + Sync + 'static,
+{
+    /// Wait for the next message.
+    ///
+    /// Unlike `observe()` nothing is stopped on a failure, that is up to the receiver.
+    /// In local execution mode, the tasks make progress while this is awaited.
+    pub async fn recv(&self) -> ControlMessage<PoolError, StopReason> {
+        let shared = &self.spawner.shared;
+        loop {
+            let message = shared.next_message().await.expect(INTERNAL_CHANNEL);
+            return match message {
+                Message::Completed(task, outcome) => {
+                    shared.outstanding.fetch_sub(1, Ordering::SeqCst);
+                    ControlMessage::Failed { task, outcome }
+                }
+                Message::Stop(why) => ControlMessage::Stop(why),
+                Message::StopOk => ControlMessage::StopOk,
+                Message::PartnerFailed(error) => ControlMessage::PartnerFailed(error),
+                Message::StopByTask(task, why) => ControlMessage::StopByTask { task, why },
+                Message::Idle => {
+                    shared.idle_queued.store(false, Ordering::SeqCst);
+                    // Stale if tasks were spawned since.
+                    if shared.outstanding.load(Ordering::SeqCst) > 0 {
+                        continue;
+                    }
+                    ControlMessage::Idle
+                }
+            };
+        }
+    }
+}
+
+/// Registers tasks with the pool, handing back their wrapped futures to be executed elsewhere.
+pub struct TaskWrapper<PoolError, StopReason = PoolError>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Send + Sync + 'static,
+{
+    spawner: Spawner<PoolError, StopReason>,
+    pool: Assembly<PoolError, StopReason>,
+}
+
+impl<PoolError, StopReason> TaskWrapper<PoolError, StopReason>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Send + Sync + 'static,
+{
+    /// Register a task and wrap `future` like the pool does when spawning it, without executing it.
+    ///
+    /// The wrapper reports to the `ControlReceiver` and obeys the `StopTrigger`, it has to be polled to completion by the caller.
+    /// If the pool is already stopping, the task is cancelled right away and the wrapper does nothing.
+    pub fn wrap<Fut>(&self, future: Fut) -> (TaskId, BoxFuture<'static, ()>)
+    where
+        Fut: Future<Output = Result<(), PoolError>> + Send + 'static,
+    {
+        let (id, registration) = self.spawner.register(None);
+        let wrapper = match registration {
+            Some((stopped, state)) => self
+                .spawner
+                .wrap(id, stopped, state, future, TaskOptions::default())
+                .boxed(),
+            None => async {}.boxed(),
+        };
+        (id, wrapper)
+    }
+}
+
+/// Sends the stop signal to the tasks of the pool.
+pub struct StopTrigger<PoolError, StopReason = PoolError>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Send + Sync + 'static,
+{
+    spawner: Spawner<PoolError, StopReason>,
+    _pool: Assembly<PoolError, StopReason>,
+}
+
+impl<PoolError, StopReason> StopTrigger<PoolError, StopReason>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Send + Sync + 'static,
+{
+    /// Send the stop signal to all running tasks, returning them. Tasks registered afterwards are cancelled right away.
+    pub fn broadcast(&self) -> Vec<TaskId> {
+        self.spawner.shared.broadcast_stop()
+    }
+}
+
+impl<PoolError, StopReason> StoppableThreadPool<PoolError, StopReason>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Send + Sync + 'static,
+{
+    /// Take the pool apart into its control machinery, see `PoolParts`.
+    ///
+    /// Futures registered by `add()` are launched first.
+    /// The parts keep the pool running, even when moved apart. Once all of them are dropped without being passed to `from_parts()`,
+    /// the pool shuts down as if it was dropped, tasks are sent the stop signal and nothing observes them anymore.
+    pub fn into_parts(mut self) -> PoolParts<PoolError, StopReason> {
+        self.start();
+        let spawner = self.spawner.clone();
+        let pool = Arc::new(Mutex::new(Some(self)));
+        PoolParts {
+            control: ControlReceiver {
+                spawner: spawner.clone(),
+                _pool: pool.clone(),
+            },
+            tasks: TaskWrapper {
+                spawner: spawner.clone(),
+                pool: pool.clone(),
+            },
+            stop: StopTrigger {
+                spawner,
+                _pool: pool,
+            },
+        }
+    }
+
+    /// Reassemble a pool taken apart by `into_parts()`.
+    pub fn from_parts(parts: PoolParts<PoolError, StopReason>) -> Self {
+        let mut pool = parts
+            .tasks
+            .pool
+            .lock()
+            .unwrap()
+            .take()
+            .expect("the pool is taken back only once");
+        // The parts may have been driven in the meantime, `poll_observe()` starts over.
+        pool.polled = Mutex::new(Polled::NotStarted);
+        pool
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::atomic::Ordering, thread};
+
+    use futures::{executor::block_on, future::pending};
+
+    use super::{ControlMessage, PoolParts};
+    use crate::{StoppableThreadPool, TaskOutcome};
+
+    #[test]
+    fn drive_parts() {
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        pool.spawn(pending());
+        let parts = pool.into_parts();
+        // Taking the pool apart does not stop it.
+        assert!(!parts.stop.spawner.shared.stopping.load(Ordering::Acquire));
+        let (task, wrapper) = parts.tasks.wrap(async { Err("fail".to_string()) });
+        thread::spawn(move || block_on(wrapper));
+
+        match block_on(parts.control.recv()) {
+            ControlMessage::Failed {
+                task: failed,
+                outcome: TaskOutcome::Failed(error),
+            } => assert_eq!((failed, error.as_str()), (task, "fail")),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(parts.stop.broadcast().len(), 1);
+
+        let pool = StoppableThreadPool::from_parts(parts);
+        assert_eq!(block_on(pool.observe()), Ok(()));
+    }
+
+    #[test]
+    fn dropped_parts_stop_the_tasks() {
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        let (tx, rx) = async_std::channel::unbounded::<()>();
+        pool.spawn(async move {
+            let _alive = tx;
+            pending().await
+        });
+        let PoolParts {
+            control,
+            tasks,
+            stop,
+        } = pool.into_parts();
+        drop((control, tasks));
+        // One part left keeps the pool running.
+        assert!(!stop.spawner.shared.stopping.load(Ordering::Acquire));
+        drop(stop);
+        // The task was dropped, and with it the sender.
+        assert!(block_on(rx.recv()).is_err());
+    }
+}
@@ -0,0 +1,104 @@
+use std::{
+    sync::atomic::Ordering,
+    task::{Context, Poll},
+};
+
+use futures::future::{BoxFuture, FutureExt};
+
+use crate::{plain_result, Failure, Message, StoppableThreadPool, INTERNAL_CHANNEL};
+
+/// Where `StoppableThreadPool::poll_observe()` is at.
+pub(crate) enum Polled<PoolError, StopReason> {
+    NotStarted,
+    Observing(BoxFuture<'static, Result<(), Failure<PoolError, StopReason>>>),
+    Done,
+}
+
+impl<PoolError, StopReason> StoppableThreadPool<PoolError, StopReason>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Send + Sync + 'static,
+{
+    /// Same as `stop()` without awaiting, for poll loops driving `poll_observe()`.
+    ///
+    /// The request is queued right away, the next poll of `poll_observe()` acts upon it.
+    pub fn try_stop(&self, why: StopReason) {
+        self.spawner.shared.stopping.store(true, Ordering::Release);
+        self.spawner
+            .control_sender
+            .try_send(Message::Stop(why))
+            .expect(INTERNAL_CHANNEL)
+    }
+}
+
+impl<PoolError, StopReason> StoppableThreadPool<PoolError, StopReason>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Into<PoolError> + Send + Sync + 'static,
+{
+    /// Poll-based `observe()`, for driving the pool from a poll loop without awaiting.
+    ///
+    /// Behaves exactly like `observe()`, including the stop broadcast on the first failure and resuming task panics.
+    /// Once it returned `Poll::Ready` it returns `Poll::Pending` forever, like a fused future.
+    /// Stop requests can be issued from the same loop through `try_stop()`.
+    pub fn poll_observe(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), PoolError>> {
+        let polled = self.polled.get_mut().unwrap();
+        if let Polled::NotStarted = polled {
+            let shared = self.spawner.shared.clone();
+            *polled = Polled::Observing(async move { shared.observe().await }.boxed());
+        }
+        let observing = match polled {
+            Polled::Observing(observing) => observing,
+            _ => return Poll::Pending,
+        };
+        let result = futures::ready!(observing.poll_unpin(cx));
+        *polled = Polled::Done;
+        Poll::Ready(plain_result(result))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::task::{Context, Poll};
+
+    use futures::{future::pending, task::noop_waker};
+
+    use crate::StoppableThreadPool;
+
+    #[test]
+    fn poll_loop() {
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        pool.spawn(pending())
+            .spawn(async { Err("fail".to_string()) });
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        let result = loop {
+            if let Poll::Ready(result) = pool.poll_observe(&mut cx) {
+                break result;
+            }
+            std::thread::yield_now();
+        };
+        assert_eq!(result, Err("fail".to_string()));
+        assert!(pool.is_stopping());
+        assert_eq!(pool.poll_observe(&mut cx), Poll::Pending);
+    }
+
+    #[test]
+    fn stop_from_poll_loop() {
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        pool.spawn(pending());
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        assert_eq!(pool.poll_observe(&mut cx), Poll::Pending);
+        pool.try_stop("stop".to_string());
+        let result = loop {
+            if let Poll::Ready(result) = pool.poll_observe(&mut cx) {
+                break result;
+            }
+            std::thread::yield_now();
+        };
+        assert_eq!(result, Err("stop".to_string()));
+    }
+}
@@ -0,0 +1,177 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    task::{Poll, Waker},
+};
+
+use futures::future::poll_fn;
+
+use crate::TaskOutcome;
+
+/// Snapshot of how many of a pool's tasks finished, see `StoppableThreadPool::progress()`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Progress {
+    completed: usize,
+    failed: usize,
+    cancelled: usize,
+    total_spawned: usize,
+}
+
+impl Progress {
+    /// Number of tasks which completed successfully.
+    pub fn completed(&self) -> usize {
+        self.completed
+    }
+
+    /// Number of tasks which returned an error or panicked.
+    pub fn failed(&self) -> usize {
+        self.failed
+    }
+
+    /// Number of tasks which were cancelled or timed out.
+    pub fn cancelled(&self) -> usize {
+        self.cancelled
+    }
+
+    /// Number of tasks spawned so far, respawns included.
+    pub fn total_spawned(&self) -> usize {
+        self.total_spawned
+    }
+
+    /// Number of tasks which finished, no matter how.
+    pub fn finished(&self) -> usize {
+        self.completed + self.failed + self.cancelled
+    }
+}
+
+/// Watches the progress of a pool, created by `StoppableThreadPool::progress()`.
+///
+/// Watching does not consume anything the pool reports, so it does not interfere with observing the pool.
+pub struct ProgressWatch {
+    state: Arc<ProgressState>,
+    seen: u64,
+    /// The slot of the watch's waker, so polling `changed()` again replaces it.
+    id: usize,
+}
+
+impl ProgressWatch {
+    pub(crate) fn new(state: Arc<ProgressState>) -> ProgressWatch {
+        let (seen, id) = {
+            let mut inner = state.inner.lock().unwrap();
+            inner.next_watch += 1;
+            (inner.version, inner.next_watch)
+        };
+        ProgressWatch { state, seen, id }
+    }
+
+    /// The current progress, marking it as seen.
+    pub fn get(&mut self) -> Progress {
+        let inner = self.state.inner.lock().unwrap();
+        self.seen = inner.version;
+        inner.progress
+    }
+
+    /// Wait until the progress changed since it was last seen, and return it.
+    pub async fn changed(&mut self) -> Progress {
+        let state = self.state.clone();
+        let (seen, id) = (self.seen, self.id);
+        let (version, progress) = poll_fn(|cx| {
+            let mut inner = state.inner.lock().unwrap();
+            if inner.version != seen {
+                return Poll::Ready((inner.version, inner.progress));
+            }
+            inner.wakers.insert(id, cx.waker().clone());
+            Poll::Pending
+        })
+        .await;
+        self.seen = version;
+        progress
+    }
+}
+
+impl Clone for ProgressWatch {
+    fn clone(&self) -> Self {
+        let mut watch = ProgressWatch::new(self.state.clone());
+        watch.seen = self.seen;
+        watch
+    }
+}
+
+impl Drop for ProgressWatch {
+    fn drop(&mut self) {
+        self.state.inner.lock().unwrap().wakers.remove(&self.id);
+    }
+}
+
+/// Progress counters shared by a pool and its watches.
+#[derive(Default)]
+pub(crate) struct ProgressState {
+    inner: Mutex<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    progress: Progress,
+    version: u64,
+    /// The waker of each waiting watch, by the watch's id.
+    wakers: HashMap<usize, Waker>,
+    next_watch: usize,
+}
+
+impl ProgressState {
+    pub(crate) fn spawned(&self) {
+        self.update(|progress| progress.total_spawned += 1);
+    }
+
+    pub(crate) fn finished<PoolError>(&self, outcome: &TaskOutcome<PoolError>) {
+        self.update(|progress| match outcome {
+            TaskOutcome::Completed => progress.completed += 1,
+            TaskOutcome::Failed(_) | TaskOutcome::Panicked(_) => progress.failed += 1,
+            TaskOutcome::Cancelled | TaskOutcome::TimedOut => progress.cancelled += 1,
+        });
+    }
+
+    fn update(&self, f: impl FnOnce(&mut Progress)) {
+        let wakers = {
+            let mut inner = self.inner.lock().unwrap();
+            f(&mut inner.progress);
+            inner.version += 1;
+            std::mem::take(&mut inner.wakers)
+        };
+        for waker in wakers.into_values() {
+            waker.wake();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, task::Context};
+
+    use futures::{future::FutureExt, task::noop_waker};
+
+    use super::{ProgressState, ProgressWatch};
+
+    #[test]
+    fn one_waker_per_watch() {
+        let state = Arc::new(ProgressState::default());
+        let mut watch = ProgressWatch::new(state.clone());
+        let mut other = watch.clone();
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        {
+            let mut changed = watch.changed().boxed();
+            for _ in 0..10 {
+                assert!(changed.poll_unpin(&mut cx).is_pending());
+            }
+            assert!(other.changed().boxed().poll_unpin(&mut cx).is_pending());
+            assert_eq!(state.inner.lock().unwrap().wakers.len(), 2);
+        }
+        drop(other);
+        assert_eq!(state.inner.lock().unwrap().wakers.len(), 1);
+
+        state.spawned();
+        assert!(state.inner.lock().unwrap().wakers.is_empty());
+        assert_eq!(watch.get().total_spawned(), 1);
+    }
+}
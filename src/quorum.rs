@@ -0,0 +1,268 @@
+use std::{error::Error, fmt, sync::atomic::Ordering};
+
+use futures::{future::FutureExt, select};
+
+use crate::{CauseKind, Message, StoppableThreadPool, TaskId, TaskOutcome, INTERNAL_CHANNEL};
+
+/// Why `StoppableThreadPool::observe_quorum()` did not reach its quorum.
+#[derive(Debug)]
+pub enum QuorumError<PoolError, StopReason = PoolError> {
+    /// Too few tasks are left which could still complete successfully, `failures` lists the failed and panicked ones.
+    Unreachable {
+        failures: Vec<(TaskId, TaskOutcome<PoolError>)>,
+    },
+    /// The pool was stopped by the user or one of its tasks before the quorum was reached.
+    Stopped(StopReason),
+    /// `observe_both()` stopped the pool because the other pool failed with this error.
+    PartnerFailed(PoolError),
+}
+
+impl<PoolError, StopReason> fmt::Display for QuorumError<PoolError, StopReason>
+where
+    StopReason: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuorumError::Unreachable { failures } => {
+                write!(
+                    f,
+                    "quorum unreachable after {} failed tasks",
+                    failures.len()
+                )
+            }
+            QuorumError::Stopped(why) => write!(f, "stopped: {}", why),
+            QuorumError::PartnerFailed(_) => write!(f, "stopped, the partner pool failed"),
+        }
+    }
+}
+
+impl<PoolError, StopReason> Error for QuorumError<PoolError, StopReason>
+where
+    PoolError: fmt::Debug,
+    StopReason: Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            QuorumError::Unreachable { .. } | QuorumError::PartnerFailed(_) => None,
+            QuorumError::Stopped(why) => Some(why),
+        }
+    }
+}
+
+impl<PoolError, StopReason> StoppableThreadPool<PoolError, StopReason>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Send + Sync + 'static,
+{
+    /// Observe the pool until `k` of its tasks completed successfully, then stop the remaining ones.
+    ///
+    /// Failing tasks don't stop the pool while the quorum can still be reached, the observation fails once too few tasks are left which could succeed.
+    /// In both cases the remaining tasks are asked to stop. A `stop()` ends the observation right away.
+    /// While observing, a failure does not mark the pool as stopping, so tasks can still be spawned and respawned, and stop the pool through `TaskControl`.
+    /// Errors ignored by the interceptor count as failures.
+    /// With `k` equal to the number of tasks this behaves like `observe_detailed()`, with `k` being zero it stops the pool right away.
+    /// Either way the pool is finished afterwards, the finalizers registered with `on_shutdown()` ran and the subscriptions ended.
+    pub async fn observe_quorum(&self, k: usize) -> Result<(), QuorumError<PoolError, StopReason>> {
+        let shared = &self.spawner.shared;
+        shared.tolerant.store(true, Ordering::Release);
+        let result = self.observe_until_quorum(k).await;
+        shared.tolerant.store(false, Ordering::Release);
+        shared
+            .finish(match &result {
+                Ok(()) => Ok(()),
+                Err(QuorumError::Unreachable { .. }) => Err(CauseKind::QuorumUnreachable),
+                Err(QuorumError::Stopped(_)) => Err(CauseKind::Stopped),
+                Err(QuorumError::PartnerFailed(_)) => Err(CauseKind::StoppedByPartner),
+            })
+            .await;
+        result
+    }
+
+    async fn observe_until_quorum(
+        &self,
+        k: usize,
+    ) -> Result<(), QuorumError<PoolError, StopReason>> {
+        let shared = &self.spawner.shared;
+        shared.release();
+        let mut watch = self.progress();
+        let mut failures = Vec::new();
+        loop {
+            let progress = watch.get();
+            if progress.completed() >= k {
+                shared.announce_stop(CauseKind::QuorumReached);
+                shared.unlink();
+                shared.broadcast_stop();
+                return Ok(());
+            }
+            // Tasks whose failure was not received yet are counted as possible successes until it is.
+            // Ignored errors are never received, they are ruled out right away.
+            let ignored = shared.ignored.load(Ordering::Acquire);
+            let possible =
+                progress.total_spawned() - progress.cancelled() - failures.len() - ignored;
+            let done = shared.outstanding.load(Ordering::SeqCst) == 0;
+            if possible < k || done {
+                shared.announce_stop(CauseKind::QuorumUnreachable);
+                shared.broadcast_stop();
+                return Err(QuorumError::Unreachable { failures });
+            }
+            select! {
+                _ = watch.changed().fuse() => (),
+                message = shared.next_message().fuse() => match message.expect(INTERNAL_CHANNEL) {
+                    Message::Completed(task, outcome) => {
+                        shared.outstanding.fetch_sub(1, Ordering::SeqCst);
+                        failures.push((task, outcome));
+                    }
+                    Message::Stop(why) => {
+                        #[cfg(feature = "metrics")]
+                        shared.metrics().stop_requested();
+                        shared.announce_stop(CauseKind::Stopped);
+                        shared.broadcast_stop();
+                        return Err(QuorumError::Stopped(why));
+                    }
+                    Message::StopByTask(task, Some(why)) => {
+                        #[cfg(feature = "metrics")]
+                        shared.metrics().stop_requested();
+                        shared.announce_stop(CauseKind::StoppedByTask(task));
+                        shared.broadcast_stop();
+                        return Err(QuorumError::Stopped(why));
+                    }
+                    Message::StopByTask(task, None) => {
+                        shared.stop_ok(Some(task));
+                        return Ok(());
+                    }
+                    Message::StopOk => {
+                        shared.stop_ok(None);
+                        return Ok(());
+                    }
+                    Message::PartnerFailed(error) => {
+                        shared.announce_stop(CauseKind::StoppedByPartner);
+                        shared.broadcast_stop();
+                        return Err(QuorumError::PartnerFailed(error));
+                    }
+                    Message::Idle => shared.idle_queued.store(false, Ordering::SeqCst),
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use futures::{executor::block_on, future::pending, join, stream::StreamExt};
+
+    use super::QuorumError;
+    use crate::{ErrorDecision, PoolEvent, StoppableThreadPool, TaskOutcome};
+
+    #[test]
+    fn quorum_reached() {
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        pool.spawn(async { Err("replica down".to_string()) })
+            .spawn(async { Ok(()) })
+            .spawn(async { Ok(()) })
+            .spawn(pending());
+        assert!(block_on(pool.observe_quorum(2)).is_ok());
+        assert!(pool.is_stopping());
+    }
+
+    #[test]
+    fn quorum_unreachable() {
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        pool.spawn(async { Err("a".to_string()) })
+            .spawn(async { Err("b".to_string()) })
+            .spawn(pending());
+        match block_on(pool.observe_quorum(2)) {
+            Err(QuorumError::Unreachable { failures }) => {
+                assert_eq!(failures.len(), 2);
+                assert!(failures
+                    .iter()
+                    .all(|(_, outcome)| matches!(outcome, TaskOutcome::Failed(_))));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn quorum_stopped() {
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        pool.spawn(pending());
+        block_on(async {
+            pool.stop("stop".to_string()).await;
+            assert!(matches!(
+                pool.observe_quorum(1).await,
+                Err(QuorumError::Stopped(why)) if why == "stop"
+            ));
+        });
+        assert!(block_on(pool.observe_quorum(0)).is_ok());
+    }
+
+    #[test]
+    fn quorum_finishes_pool() {
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        let (tx, rx) = async_std::channel::unbounded();
+        pool.spawn(async { Ok(()) })
+            .spawn(pending())
+            .on_shutdown(move || async move {
+                tx.send(()).await.unwrap();
+                Ok(())
+            });
+        let events = pool.subscribe();
+        assert!(block_on(pool.observe_quorum(1)).is_ok());
+        assert_eq!(rx.try_recv(), Ok(()));
+        assert_eq!(
+            block_on(events.collect::<Vec<_>>()).last(),
+            Some(&PoolEvent::PoolFinished { result: Ok(()) })
+        );
+    }
+
+    #[test]
+    fn respawn_after_tolerated_failure() {
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        let (tx, rx) = async_std::channel::unbounded::<()>();
+        let runs = Arc::new(AtomicUsize::new(0));
+        let handle = pool.spawn_factory(move || {
+            let first = runs.fetch_add(1, Ordering::SeqCst) == 0;
+            let rx = rx.clone();
+            async move {
+                if first {
+                    let _ = rx.recv().await;
+                    return Err("flaky".to_string());
+                }
+                Ok(())
+            }
+        });
+        // Keeps the quorum reachable until the respawned task succeeded.
+        pool.spawn(pending());
+        block_on(async {
+            let mut watch = pool.progress();
+            let respawned = async {
+                // Fails only once the quorum is observed.
+                tx.send(()).await.unwrap();
+                while watch.changed().await.failed() == 0 {}
+                assert!(!pool.is_stopping());
+                handle.respawn()
+            };
+            let (reached, respawned) = join!(pool.observe_quorum(1), respawned);
+            assert_eq!(respawned, Ok(()));
+            assert!(reached.is_ok());
+        });
+    }
+
+    #[test]
+    fn ignored_errors_rule_out_quorum() {
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        pool.set_error_interceptor(|_: &String| ErrorDecision::Ignore);
+        pool.spawn(async { Err("a".to_string()) })
+            .spawn(async { Err("b".to_string()) })
+            .spawn(pending());
+        // Unreachable without waiting for the pending task.
+        assert!(matches!(
+            block_on(pool.observe_quorum(2)),
+            Err(QuorumError::Unreachable { .. })
+        ));
+    }
+}
@@ -0,0 +1,53 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::{clock::Instant, Clock};
+
+/// Token bucket throttling how fast tasks start, see `StoppableThreadPool::set_start_rate()`.
+pub(crate) struct StartRate {
+    per_second: f64,
+    burst: f64,
+    bucket: Mutex<Bucket>,
+    clock: Arc<dyn Clock>,
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl StartRate {
+    pub(crate) fn new(per_second: u32, burst: u32, clock: Arc<dyn Clock>) -> StartRate {
+        let burst = f64::from(burst.max(1));
+        StartRate {
+            per_second: f64::from(per_second),
+            burst,
+            bucket: Mutex::new(Bucket {
+                tokens: burst,
+                refilled: clock.now(),
+            }),
+            clock,
+        }
+    }
+
+    /// Wait until a token is available and take it.
+    pub(crate) async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock().unwrap();
+                let now = self.clock.now();
+                let refill = now.duration_since(bucket.refilled).as_secs_f64() * self.per_second;
+                bucket.tokens = (bucket.tokens + refill).min(self.burst);
+                bucket.refilled = now;
+                if bucket.tokens >= 1.0 {
+                    bucket.tokens -= 1.0;
+                    return;
+                }
+                Duration::from_secs_f64((1.0 - bucket.tokens) / self.per_second)
+            };
+            self.clock.sleep(wait).await;
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use std::{sync::atomic::Ordering, time::Duration};
+
+use crate::{StoppableThreadPool, TaskId, COMPLETED, RUNNING};
+
+/// A task which was alive when `StoppableThreadPool::tasks()` was called.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskInfo {
+    id: TaskId,
+    context: Option<String>,
+    elapsed: Duration,
+    stop_requested: bool,
+}
+
+impl TaskInfo {
+    /// The id of the task.
+    pub fn id(&self) -> TaskId {
+        self.id
+    }
+
+    /// The context of the task, if it was spawned with one.
+    pub fn context(&self) -> Option<&str> {
+        self.context.as_deref()
+    }
+
+    /// How long the task is running since its first poll.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Whether the task was sent the stop signal but did not exit yet, because it is running its cleanup for example.
+    pub fn stop_requested(&self) -> bool {
+        self.stop_requested
+    }
+}
+
+impl<PoolError, StopReason> StoppableThreadPool<PoolError, StopReason>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Send + Sync + 'static,
+{
+    /// A snapshot of the tasks which were polled at least once and did not exit yet, in spawn order.
+    ///
+    /// Unlike `running_tasks()` this includes the tasks which were sent the stop signal and are still on their way out,
+    /// marked by `TaskInfo::stop_requested()`. Tasks waiting for their first poll, behind a start barrier or dependencies for example, are not included.
+    /// Only the metadata of the tasks is copied, so this is cheap and can be called from any thread, also while the pool is stopping.
+    pub fn tasks(&self) -> Vec<TaskInfo> {
+        let now = self.spawner.shared.clock.now();
+        let tasks = self.spawner.shared.tasks.lock().unwrap();
+        tasks
+            .iter()
+            .enumerate()
+            .filter(|(_, task)| !task.exited.load(Ordering::Acquire))
+            .filter_map(|(id, task)| {
+                let state = task.state.load(Ordering::Acquire);
+                if state == COMPLETED {
+                    return None;
+                }
+                let started = (*task.started.lock().unwrap())?;
+                Some(TaskInfo {
+                    id: TaskId(id),
+                    context: task.context.clone(),
+                    elapsed: now - started,
+                    stop_requested: state != RUNNING,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{channel::oneshot, executor::block_on, future::pending};
+
+    use crate::{StoppableThreadPool, TaskId};
+
+    #[test]
+    fn task_registry() {
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        let (sync_started, synced) = oneshot::channel();
+        let (cleanup_started, cleaning) = oneshot::channel();
+        let (release, released) = oneshot::channel::<()>();
+        pool.spawn_with_context("sync", async move {
+            let _ = sync_started.send(());
+            pending().await
+        })
+        .spawn_with_cleanup(
+            async move {
+                let _ = cleanup_started.send(());
+                pending().await
+            },
+            async move {
+                let _ = released.await;
+            },
+            None,
+        );
+        block_on(async { synced.await.and(cleaning.await) }).unwrap();
+
+        let tasks = pool.tasks();
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].context(), Some("sync"));
+        assert!(tasks.iter().all(|task| !task.stop_requested()));
+
+        block_on(pool.stop("stop".to_string()));
+        assert_eq!(block_on(pool.observe()), Err("stop".to_string()));
+        // Held up by its cleanup.
+        let cleaning = pool.tasks().into_iter().find(|task| task.id() == TaskId(1));
+        assert!(cleaning.unwrap().stop_requested());
+        assert!(pool.running_tasks().is_empty());
+
+        release.send(()).unwrap();
+        assert_eq!(block_on(pool.await_termination(None)), Ok(()));
+        assert!(pool.tasks().is_empty());
+    }
+}
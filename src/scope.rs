@@ -0,0 +1,169 @@
+use std::{
+    marker::PhantomData,
+    panic::{catch_unwind, resume_unwind, AssertUnwindSafe},
+    pin::Pin,
+    sync::{Arc, Condvar, Mutex},
+    task::{Context, Poll},
+};
+
+use futures::{
+    executor::{block_on, ThreadPool},
+    future::{BoxFuture, Future, FutureExt},
+};
+
+use crate::{Cause, StoppableThreadPool, TaskOptions, INTERNAL_CHANNEL};
+
+/// Run `f` with a `Scope` to spawn futures borrowing from the enclosing function, in the spirit of `std::thread::scope`.
+///
+/// All futures spawned on the scope are observed like the tasks of a `StoppableThreadPool` executing on `pool`:
+/// the first error stops the remaining ones and is returned here.
+/// This blocks the calling thread until every spawned future has completed or was dropped by the pool, so borrows can't dangle.
+/// There is no async variant: a future awaiting the scope could be forgotten while the tasks are still running.
+///
+/// A panic in `f` or in a spawned future is resumed once all futures are gone.
+pub fn stoppable_scope<'env, PoolError, F>(pool: ThreadPool, f: F) -> Result<(), PoolError>
+where
+    PoolError: Send + Sync + 'static,
+    F: for<'scope> FnOnce(&'scope Scope<'scope, 'env, PoolError>),
+{
+    let scope = Scope {
+        pool: StoppableThreadPool::new_with_pool(pool),
+        live: Arc::new(Live::default()),
+        scope: PhantomData,
+        env: PhantomData,
+    };
+    let spawned = catch_unwind(AssertUnwindSafe(|| f(&scope)));
+    let result = match spawned {
+        Ok(()) => block_on(scope.pool.observe_detailed()),
+        Err(_) => {
+            scope.pool.spawner.shared.broadcast_stop();
+            Ok(())
+        }
+    };
+    scope.live.wait();
+
+    if let Err(payload) = spawned {
+        resume_unwind(payload);
+    }
+    result.map_err(|failure| match failure.cause {
+        Cause::TaskPanicked { task, message } => panic!("{} panicked: {}", task, message),
+        _ => failure.into_error().expect(INTERNAL_CHANNEL),
+    })
+}
+
+/// Spawns futures which may borrow data outliving the scope, see `stoppable_scope()`.
+pub struct Scope<'scope, 'env: 'scope, PoolError>
+where
+    PoolError: Send + Sync + 'static,
+{
+    pool: StoppableThreadPool<PoolError>,
+    live: Arc<Live>,
+    scope: PhantomData<&'scope mut &'scope ()>,
+    env: PhantomData<&'env mut &'env ()>,
+}
+
+impl<'scope, 'env, PoolError> Scope<'scope, 'env, PoolError>
+where
+    PoolError: Send + Sync + 'static,
+{
+    /// Spawn a future borrowing data of the enclosing function onto the pool.
+    pub fn spawn<Fut>(&'scope self, future: Fut) -> &'scope Self
+    where
+        Fut: Future<Output = Result<(), PoolError>> + Send + 'scope,
+    {
+        *self.live.count.lock().unwrap() += 1;
+        let tracked: BoxFuture<'scope, Result<(), PoolError>> = Box::pin(Tracked {
+            future: future.boxed(),
+            _guard: LiveGuard(self.live.clone()),
+        });
+        // SAFETY: `stoppable_scope()` does not return before every `LiveGuard` was dropped,
+        // which happens only after the future borrowing from `'scope` was dropped.
+        let tracked: BoxFuture<'static, Result<(), PoolError>> =
+            unsafe { std::mem::transmute(tracked) };
+        self.pool.spawner.spawn(tracked, TaskOptions::default());
+        self
+    }
+
+    /// Stop all futures of the scope, the scope then returns `why`.
+    pub async fn stop(&self, why: PoolError) {
+        self.pool.stop(why).await
+    }
+}
+
+/// Number of spawned futures which were not dropped yet.
+#[derive(Default)]
+struct Live {
+    count: Mutex<usize>,
+    gone: Condvar,
+}
+
+impl Live {
+    fn wait(&self) {
+        let mut count = self.count.lock().unwrap();
+        while *count > 0 {
+            count = self.gone.wait(count).unwrap();
+        }
+    }
+}
+
+struct LiveGuard(Arc<Live>);
+
+impl Drop for LiveGuard {
+    fn drop(&mut self) {
+        *self.0.count.lock().unwrap() -= 1;
+        self.0.gone.notify_all();
+    }
+}
+
+/// Fields are dropped in declaration order, so the guard outlives the future.
+struct Tracked<'scope, PoolError> {
+    future: BoxFuture<'scope, Result<(), PoolError>>,
+    _guard: LiveGuard,
+}
+
+impl<PoolError> Future for Tracked<'_, PoolError> {
+    type Output = Result<(), PoolError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.future.as_mut().poll(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use futures::{executor::ThreadPool, future::pending};
+
+    use super::stoppable_scope;
+
+    #[test]
+    fn borrow_locals() {
+        let data = vec![1, 2, 3];
+        let sum = AtomicUsize::new(0);
+
+        let result: Result<(), String> = stoppable_scope(ThreadPool::new().unwrap(), |scope| {
+            for value in &data {
+                let sum = &sum;
+                scope.spawn(async move {
+                    sum.fetch_add(*value, Ordering::SeqCst);
+                    Ok(())
+                });
+            }
+        });
+        assert_eq!(result, Ok(()));
+        assert_eq!(sum.load(Ordering::SeqCst), 6);
+    }
+
+    #[test]
+    fn error_stops_scope() {
+        let data = String::from("fail");
+
+        let result = stoppable_scope(ThreadPool::new().unwrap(), |scope| {
+            scope
+                .spawn(async { pending::<Result<(), String>>().await })
+                .spawn(async { Err(data.clone()) });
+        });
+        assert_eq!(result, Err("fail".to_string()));
+    }
+}
@@ -0,0 +1,155 @@
+use std::{
+    error::Error,
+    fmt,
+    pin::Pin,
+    sync::atomic::Ordering,
+    task::{Context, Poll},
+};
+
+use futures::{
+    future::{BoxFuture, FutureExt},
+    sink::Sink,
+};
+
+use crate::{spawner::Spawner, Failure, StoppableThreadPool, TaskOptions};
+
+/// Submits task futures to a `StoppableThreadPool` through the `Sink` interface, see `StoppableThreadPool::sink()`.
+///
+/// Closing the sink observes the pool: it resolves once all tasks completed, or with the failure that stopped the pool.
+pub struct TaskSink<PoolError, StopReason = PoolError> {
+    spawner: Spawner<PoolError, StopReason>,
+    closing: Option<BoxFuture<'static, Result<(), Failure<PoolError, StopReason>>>>,
+}
+
+impl<PoolError, StopReason> StoppableThreadPool<PoolError, StopReason>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Send + Sync + 'static,
+{
+    /// Get a `Sink` spawning every future sent into it onto the pool.
+    pub fn sink(&self) -> TaskSink<PoolError, StopReason> {
+        TaskSink {
+            spawner: self.spawner.clone(),
+            closing: None,
+        }
+    }
+}
+
+impl<PoolError, StopReason> TaskSink<PoolError, StopReason> {
+    fn check_running(&self) -> Result<(), SinkError<PoolError, StopReason>> {
+        match self.spawner.shared.stopping.load(Ordering::Acquire) || self.closing.is_some() {
+            true => Err(SinkError::Stopped),
+            false => Ok(()),
+        }
+    }
+}
+
+impl<PoolError, StopReason> Sink<BoxFuture<'static, Result<(), PoolError>>>
+    for TaskSink<PoolError, StopReason>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Send + Sync + 'static,
+{
+    type Error = SinkError<PoolError, StopReason>;
+
+    fn poll_ready(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(self.check_running())
+    }
+
+    fn start_send(
+        self: Pin<&mut Self>,
+        future: BoxFuture<'static, Result<(), PoolError>>,
+    ) -> Result<(), Self::Error> {
+        self.check_running()?;
+        self.spawner.spawn(future, TaskOptions::default());
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let shared = self.spawner.shared.clone();
+        let closing = self
+            .closing
+            .get_or_insert_with(|| async move { shared.observe().await }.boxed());
+        closing.as_mut().poll(cx).map_err(SinkError::Failed)
+    }
+}
+
+/// Why a `TaskSink` did not accept a task or failed to close.
+#[derive(Debug)]
+pub enum SinkError<PoolError, StopReason = PoolError> {
+    /// The pool is stopping or the sink was closed, the task was not spawned.
+    Stopped,
+    /// Closing the sink observed the pool failing.
+    Failed(Failure<PoolError, StopReason>),
+}
+
+impl<PoolError, StopReason> fmt::Display for SinkError<PoolError, StopReason>
+where
+    PoolError: fmt::Display,
+    StopReason: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SinkError::Stopped => write!(f, "the pool does not accept tasks anymore"),
+            SinkError::Failed(failure) => failure.fmt(f),
+        }
+    }
+}
+
+impl<PoolError, StopReason> Error for SinkError<PoolError, StopReason>
+where
+    PoolError: Error + 'static,
+    StopReason: Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SinkError::Stopped => None,
+            SinkError::Failed(failure) => failure.source(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{
+        executor::block_on,
+        future::{pending, FutureExt},
+        sink::SinkExt,
+        stream::{self, StreamExt},
+    };
+
+    use super::SinkError;
+    use crate::StoppableThreadPool;
+
+    #[test]
+    fn forward_tasks() {
+        let pool = StoppableThreadPool::<String>::new().unwrap();
+        let tasks = stream::iter(0..10).map(|_| Ok(async { Ok(()) }.boxed()));
+        block_on(async { tasks.forward(pool.sink()).await.unwrap() });
+        assert_eq!(pool.progress().get().completed(), 10);
+    }
+
+    #[test]
+    fn stopped_pool_rejects_tasks() {
+        let pool = StoppableThreadPool::<String>::new().unwrap();
+        let mut sink = pool.sink();
+        block_on(async {
+            sink.send(async { pending().await }.boxed()).await.unwrap();
+            sink.send(async { Err("fail".to_string()) }.boxed())
+                .await
+                .unwrap();
+            match sink.close().await {
+                Err(SinkError::Failed(failure)) => {
+                    assert_eq!(failure.error(), Some(&"fail".to_string()))
+                }
+                other => panic!("unexpected {:?}", other),
+            }
+            let rejected = pool.sink().send(async { Ok(()) }.boxed()).await;
+            assert!(matches!(rejected, Err(SinkError::Stopped)));
+        });
+    }
+}
@@ -0,0 +1,143 @@
+use std::{
+    sync::{atomic::Ordering, Arc},
+    time::Duration,
+};
+
+use futures::{
+    future::{Future, FutureExt},
+    pin_mut, select,
+};
+
+use crate::{PoolEvent, Shared, StoppableThreadPool, TaskId, RUNNING};
+
+/// A task which was still running when `StoppableThreadPool::running_tasks()` was called.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunningTask {
+    id: TaskId,
+    context: Option<String>,
+    elapsed: Duration,
+}
+
+impl RunningTask {
+    /// The id of the task.
+    pub fn id(&self) -> TaskId {
+        self.id
+    }
+
+    /// The context of the task, if it was spawned with one.
+    pub fn context(&self) -> Option<&str> {
+        self.context.as_deref()
+    }
+
+    /// How long the task is running since its first poll.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+}
+
+impl<PoolError, StopReason> StoppableThreadPool<PoolError, StopReason>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Send + Sync + 'static,
+{
+    /// A snapshot of the tasks which were polled at least once and did not finish yet, in spawn order.
+    ///
+    /// Tasks waiting for their first poll are not included, neither are tasks which were sent the stop signal, even if they did not exit yet;
+    /// see `tasks()` for those.
+    pub fn running_tasks(&self) -> Vec<RunningTask> {
+        let now = self.spawner.shared.clock.now();
+        let tasks = self.spawner.shared.tasks.lock().unwrap();
+        tasks
+            .iter()
+            .enumerate()
+            .filter(|(_, task)| task.state.load(Ordering::Acquire) == RUNNING)
+            .filter_map(|(id, task)| {
+                let started = (*task.started.lock().unwrap())?;
+                Some(RunningTask {
+                    id: TaskId(id),
+                    context: task.context.clone(),
+                    elapsed: now - started,
+                })
+            })
+            .collect()
+    }
+
+    /// Publish a `PoolEvent::TaskSlow` for every task spawned from now on which is still running `threshold` after its first poll.
+    pub fn set_slow_threshold(&mut self, threshold: Duration) -> &mut Self {
+        *self.spawner.shared.slow_threshold.lock().unwrap() = Some(threshold);
+        self
+    }
+}
+
+/// Await `future`, publishing a `PoolEvent::TaskSlow` for the task `id` if it takes longer than `threshold`.
+pub(crate) async fn watch<F, PoolError, StopReason>(
+    shared: Arc<Shared<PoolError, StopReason>>,
+    id: TaskId,
+    threshold: Option<Duration>,
+    future: F,
+) -> F::Output
+where
+    F: Future,
+{
+    let threshold = match threshold {
+        Some(threshold) => threshold,
+        None => return future.await,
+    };
+    let future = future.fuse();
+    let slow = shared.clock.sleep(threshold).fuse();
+    pin_mut!(future, slow);
+    select! {
+        output = future => return output,
+        () = slow => shared.subscribers.send(|| PoolEvent::TaskSlow {
+            task: id,
+            elapsed: threshold,
+        }),
+    }
+    future.await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::{executor::block_on, future::pending, FutureExt, StreamExt};
+
+    use crate::{MockClock, PoolEvent, StoppableThreadPool};
+
+    #[test]
+    fn slow_and_running_tasks() {
+        let clock = MockClock::new();
+        let mut pool = StoppableThreadPool::<String>::new_with_clock(
+            futures::executor::ThreadPool::new().unwrap(),
+            clock.clone(),
+        );
+        let mut events = pool.subscribe();
+        pool.set_slow_threshold(Duration::from_secs(10));
+        pool.spawn_with_context("hang", pending());
+
+        block_on(async {
+            let spawned = events.next().await;
+            assert!(matches!(spawned, Some(PoolEvent::TaskSpawned { .. })));
+            let slow = loop {
+                clock.advance(Duration::from_secs(10));
+                async_std::task::yield_now().await;
+                if let Some(Some(event)) = events.next().now_or_never() {
+                    break event;
+                }
+            };
+            assert!(
+                matches!(slow, PoolEvent::TaskSlow { elapsed, .. } if elapsed == Duration::from_secs(10))
+            );
+        });
+
+        let running = pool.running_tasks();
+        assert_eq!(running.len(), 1);
+        assert_eq!(running[0].context(), Some("hang"));
+        assert!(running[0].elapsed() >= Duration::from_secs(10));
+
+        // Tasks of a local pool are not polled before the pool is observed.
+        let mut pool = StoppableThreadPool::<String>::new_local();
+        pool.spawn(pending());
+        assert!(pool.running_tasks().is_empty());
+    }
+}
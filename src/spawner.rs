@@ -0,0 +1,512 @@
+use std::{
+    error::Error,
+    fmt,
+    panic::AssertUnwindSafe,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, AtomicU8, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use async_std::channel::{unbounded, Receiver, Sender};
+
+use futures::{
+    channel::oneshot,
+    executor::ThreadPool,
+    future::{pending, BoxFuture, Future, FutureExt},
+    pin_mut, select,
+    stream::Stream,
+};
+
+use crate::{
+    clock::timeout, dependency::Completion, local::Executor, slow, termination::ExitGuard,
+    JoinHandle, Message, RespawnError, Shared, Task, TaskId, TaskOutcome, CANCELLED, COMPLETED,
+    RUNNING,
+};
+
+/// Called with the outcome of a task, right before it is reported to the pool.
+pub(crate) type OnOutcome<PoolError> = Box<dyn FnOnce(&TaskOutcome<PoolError>) + Send>;
+
+/// The stop signal receiver and state of a registered task, for launching it.
+pub(crate) type Registration = (Receiver<()>, Arc<AtomicU8>);
+
+/// Per-task settings chosen at spawn time.
+pub(crate) struct TaskOptions<PoolError> {
+    pub(crate) context: Option<String>,
+    pub(crate) timeout: Option<Duration>,
+    /// Run after the task was cancelled, bounded by `cleanup_timeout`.
+    pub(crate) cleanup: Option<BoxFuture<'static, ()>>,
+    pub(crate) cleanup_timeout: Option<Duration>,
+    pub(crate) on_outcome: Option<OnOutcome<PoolError>>,
+    /// Tasks which must complete successfully before this one starts.
+    pub(crate) dependencies: Vec<Arc<Completion>>,
+}
+
+impl<PoolError> Default for TaskOptions<PoolError> {
+    fn default() -> Self {
+        TaskOptions {
+            context: None,
+            timeout: None,
+            cleanup: None,
+            cleanup_timeout: None,
+            on_outcome: None,
+            dependencies: Vec::new(),
+        }
+    }
+}
+
+/// Everything needed to launch tasks into a pool, shared by the pool and its task handles.
+pub(crate) struct Spawner<PoolError, StopReason> {
+    pub(crate) executor: Executor,
+    pub(crate) control_sender: Sender<Message<PoolError, StopReason>>,
+    pub(crate) shared: Arc<Shared<PoolError, StopReason>>,
+}
+
+impl<PoolError, StopReason> Clone for Spawner<PoolError, StopReason> {
+    fn clone(&self) -> Self {
+        Spawner {
+            executor: self.executor.clone(),
+            control_sender: self.control_sender.clone(),
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<PoolError, StopReason> Spawner<PoolError, StopReason>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Send + Sync + 'static,
+{
+    pub(crate) fn spawn<Fut>(&self, future: Fut, mut options: TaskOptions<PoolError>) -> TaskId
+    where
+        Fut: Future<Output = Result<(), PoolError>> + Send + 'static,
+    {
+        let (id, registration) = self.register(options.context.take());
+        if let Some((stopped, state)) = registration {
+            self.launch(id, stopped, state, future, options);
+        }
+        id
+    }
+
+    pub(crate) fn spawn_with_handle<Fut>(&self, future: Fut) -> JoinHandle<PoolError, StopReason>
+    where
+        PoolError: Clone,
+        Fut: Future<Output = Result<(), PoolError>> + Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        let id = self.spawn(
+            future,
+            TaskOptions {
+                on_outcome: Some(Box::new(move |outcome: &TaskOutcome<PoolError>| {
+                    let _ = tx.send(outcome.clone());
+                })),
+                ..TaskOptions::default()
+            },
+        );
+        JoinHandle::new(id, self.clone(), rx)
+    }
+
+    /// Register a new task, returning `None` in place of its stop receiver and state if the pool is already stopping or draining.
+    pub(crate) fn register(&self, context: Option<String>) -> (TaskId, Option<Registration>) {
+        self.register_with(context, false)
+            .expect("registration is only refused on request")
+    }
+
+    /// Same as `register()`, but refuses to register the task at all if the pool is closed, without leaving an entry behind.
+    pub(crate) fn try_register(&self, context: Option<String>) -> Option<(TaskId, Registration)> {
+        match self.register_with(context, true)? {
+            (id, Some(registration)) => Some((id, registration)),
+            (_, None) => unreachable!("closed pools refuse the registration"),
+        }
+    }
+
+    fn register_with(
+        &self,
+        context: Option<String>,
+        refuse: bool,
+    ) -> Option<(TaskId, Option<Registration>)> {
+        let (tx, rx) = unbounded::<()>();
+        // Checking the flag while holding the lock guarantees that the task is either seen by the stop broadcast or cancelled right here.
+        let (id, state) = {
+            let mut tasks = self.shared.tasks.lock().unwrap();
+            let closed = self.shared.stopping.load(Ordering::Acquire)
+                || self.shared.sealed.load(Ordering::Acquire);
+            if refuse && (closed || self.shared.is_finished()) {
+                return None;
+            }
+            let state = match closed {
+                true => CANCELLED,
+                false => RUNNING,
+            };
+            let state = Arc::new(AtomicU8::new(state));
+            let id = TaskId(tasks.len());
+            self.shared.task_spawned(id, context.as_ref());
+            tasks.push(Task {
+                stop: tx,
+                state: state.clone(),
+                context,
+                duration: None,
+                completion: Arc::new(Completion::new()),
+                // Cancelled tasks are never launched.
+                exited: Arc::new(AtomicBool::new(closed)),
+                abort: unbounded(),
+                polls: None,
+                priority: 0,
+                started: Arc::default(),
+                #[cfg(feature = "backtrace")]
+                backtrace: None,
+            });
+            self.shared.outstanding.fetch_add(1, Ordering::AcqRel);
+            (id, state)
+        };
+        if state.load(Ordering::Acquire) == CANCELLED {
+            // Report back like a task that received the stop signal.
+            self.shared.tasks.lock().unwrap()[id.0]
+                .completion
+                .finish(false);
+            self.shared.task_finished(id, &TaskOutcome::Cancelled);
+            self.shared
+                .report(&self.control_sender, id, TaskOutcome::Cancelled);
+            return Some((id, None));
+        }
+        Some((id, Some((rx, state))))
+    }
+
+    /// Launch the future of a task which finished before under the same id.
+    pub(crate) fn respawn<Fut>(&self, id: TaskId, future: Fut) -> Result<(), RespawnError>
+    where
+        Fut: Future<Output = Result<(), PoolError>> + Send + 'static,
+    {
+        let (rx, state) = {
+            let mut tasks = self.shared.tasks.lock().unwrap();
+            if self.shared.stopping.load(Ordering::Acquire) {
+                return Err(RespawnError::Stopping);
+            }
+            let task = &mut tasks[id.0];
+            if task.state.load(Ordering::Acquire) == RUNNING {
+                return Err(RespawnError::Running);
+            }
+            let (tx, rx) = unbounded::<()>();
+            let state = Arc::new(AtomicU8::new(RUNNING));
+            task.stop = tx;
+            task.state = state.clone();
+            task.duration = None;
+            task.completion = Arc::new(Completion::new());
+            task.exited = Arc::new(AtomicBool::new(false));
+            task.abort = unbounded();
+            task.started = Arc::default();
+            self.shared.outstanding.fetch_add(1, Ordering::AcqRel);
+            self.shared.task_spawned(id, task.context.as_ref());
+            (rx, state)
+        };
+        self.launch(id, rx, state, future, TaskOptions::default());
+        Ok(())
+    }
+
+    pub(crate) fn launch<Fut>(
+        &self,
+        id: TaskId,
+        stopped: Receiver<()>,
+        state: Arc<AtomicU8>,
+        future: Fut,
+        options: TaskOptions<PoolError>,
+    ) where
+        Fut: Future<Output = Result<(), PoolError>> + Send + 'static,
+    {
+        self.launch_on(&self.executor, id, stopped, state, future, options)
+    }
+
+    /// Same as `launch()`, but spawns the wrapper onto `executor` instead of the executor of the pool.
+    pub(crate) fn launch_on<Fut>(
+        &self,
+        executor: &Executor,
+        id: TaskId,
+        stopped: Receiver<()>,
+        state: Arc<AtomicU8>,
+        future: Fut,
+        options: TaskOptions<PoolError>,
+    ) where
+        Fut: Future<Output = Result<(), PoolError>> + Send + 'static,
+    {
+        let wrapper = self.wrap(id, stopped, state, future, options);
+        match executor {
+            Executor::ThreadPool(pool) => pool.spawn_ok(wrapper),
+            Executor::Local => self.shared.local.spawn(wrapper.boxed()),
+            #[cfg(feature = "global-executor")]
+            Executor::Global => drop(async_std::task::spawn(wrapper)),
+            #[cfg(feature = "wasm")]
+            Executor::Wasm => wasm_bindgen_futures::spawn_local(wrapper),
+        }
+    }
+
+    /// Wrap the future of a registered task, the wrapper reports back to the pool and obeys its stop signal.
+    pub(crate) fn wrap<Fut>(
+        &self,
+        id: TaskId,
+        stopped: Receiver<()>,
+        state: Arc<AtomicU8>,
+        future: Fut,
+        options: TaskOptions<PoolError>,
+    ) -> impl Future<Output = ()> + 'static
+    where
+        Fut: Future<Output = Result<(), PoolError>> + 'static,
+    {
+        let control = self.control_sender.clone();
+        let barrier = self
+            .shared
+            .start_barrier
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|(_, rx)| rx.clone());
+        let running = state.clone();
+        let shared = self.shared.clone();
+        let (completion, exited, started, abort) = {
+            let task = &self.shared.tasks.lock().unwrap()[id.0];
+            (
+                task.completion.clone(),
+                task.exited.clone(),
+                task.started.clone(),
+                task.abort.1.clone(),
+            )
+        };
+        let exit = ExitGuard {
+            exited,
+            exits: self.shared.exits.clone(),
+        };
+        let dependencies = options.dependencies;
+        let start_rate = self.shared.start_rate.lock().unwrap().clone();
+        let first_poll = started.clone();
+        let slow_threshold = *self.shared.slow_threshold.lock().unwrap();
+        let watcher = self.shared.clone();
+        let time_limit = options.timeout;
+        let on_outcome = options.on_outcome;
+        let (cleanup, cleanup_timeout) = (options.cleanup, options.cleanup_timeout);
+        let clock = self.shared.clock.clone();
+        #[cfg(feature = "backtrace")]
+        let failing = self.shared.clone();
+        let cleanup_clock = clock.clone();
+        let cleanup = async move {
+            let cleanup = match cleanup {
+                Some(cleanup) => cleanup,
+                None => return,
+            };
+            match cleanup_timeout {
+                Some(time_limit) => {
+                    let _ = timeout(&*cleanup_clock, time_limit, cleanup).await;
+                }
+                None => cleanup.await,
+            }
+        };
+        let future = async move {
+            if let Some(barrier) = barrier {
+                // The barrier is released by closing the channel.
+                let _ = barrier.recv().await;
+                if running.load(Ordering::Acquire) != RUNNING {
+                    // Cancelled while parked, the stop signal takes it from here.
+                    return pending().await;
+                }
+            }
+            for dependency in dependencies {
+                if !dependency.wait().await {
+                    // The task never runs if a dependency failed or was cancelled.
+                    return TaskOutcome::Cancelled;
+                }
+            }
+            if let Some(start_rate) = start_rate {
+                start_rate.acquire().await;
+            }
+            *first_poll.lock().unwrap() = Some(clock.now());
+            let future = slow::watch(
+                watcher,
+                id,
+                slow_threshold,
+                AssertUnwindSafe(future).catch_unwind(),
+            );
+            let output = match time_limit {
+                Some(time_limit) => match timeout(&*clock, time_limit, future).await {
+                    Some(output) => output,
+                    None => return TaskOutcome::TimedOut,
+                },
+                None => future.await,
+            };
+            // Captured while still inside the task, where the error left its future.
+            #[cfg(feature = "backtrace")]
+            if !matches!(output, Ok(Ok(()))) {
+                failing.capture_backtrace(id);
+            }
+            match output {
+                Ok(output) => TaskOutcome::from_result(output),
+                Err(payload) => TaskOutcome::from_panic(payload),
+            }
+        };
+        #[cfg(feature = "chaos")]
+        let future = match &self.shared.chaos {
+            Some(chaos) => chaos.disturb(id, future).left_future(),
+            None => future.right_future(),
+        };
+        let wrapper = async move {
+            let _exit = exit;
+            let record_duration = || {
+                let now = shared.clock.now();
+                let duration = started.lock().unwrap().map(|start| now - start);
+                shared.tasks.lock().unwrap()[id.0].duration = duration;
+            };
+            let mut stopped = stopped;
+            // The task is dropped at the end of this block, before cleaning up after it.
+            let signal = {
+                pin_mut!(future);
+                let watched = Watched {
+                    future,
+                    stopped: &mut stopped,
+                };
+                match watched.await {
+                    Watch::Finished(outcome) => {
+                        record_duration();
+                        completion.finish(matches!(outcome, TaskOutcome::Completed));
+                        // Late if the stop signal was sent, but the task finished before noticing it.
+                        let late = state
+                            .compare_exchange(
+                                RUNNING,
+                                COMPLETED,
+                                Ordering::AcqRel,
+                                Ordering::Acquire,
+                            )
+                            .is_err();
+                        // Intercepted first, so a follow-up resolved by `on_outcome` can tell whether the pool stops.
+                        let (outcome, ignored) = match late {
+                            true => (outcome, false),
+                            false => shared.intercept(outcome),
+                        };
+                        if let Some(on_outcome) = on_outcome {
+                            on_outcome(&outcome);
+                        }
+                        shared.task_finished(id, &outcome);
+                        match late {
+                            true => shared.report_late(&control, id, outcome),
+                            false => shared.report_intercepted(&control, id, outcome, ignored),
+                        }
+                        return;
+                    }
+                    Watch::Stopped(signal) => signal,
+                }
+            };
+            record_duration();
+            completion.finish(false);
+            {
+                let cleanup = cleanup.fuse();
+                // Closed by `stop_graceful()` once the grace period is over.
+                let aborted = abort.recv().fuse();
+                pin_mut!(cleanup, aborted);
+                select! {
+                    () = cleanup => (),
+                    _ = aborted => (),
+                }
+            }
+            // If the pool was dropped there is nothing left to report to.
+            if signal {
+                shared.task_finished(id, &TaskOutcome::Cancelled);
+                if let Some(on_outcome) = on_outcome {
+                    on_outcome(&TaskOutcome::Cancelled);
+                }
+                shared.report(&control, id, TaskOutcome::Cancelled);
+            }
+        };
+        wrapper
+    }
+}
+
+/// What ended a `Watched` task.
+enum Watch<T> {
+    Finished(T),
+    /// The stop signal arrived, `false` if the channel was closed because the pool was dropped.
+    Stopped(bool),
+}
+
+/// Polls a task until it finishes or the stop signal arrives, without the bookkeeping of `select!`.
+///
+/// The task is polled first, so a task which finished by the time the stop signal is noticed counts as finished, see `collect_late_completions()`.
+struct Watched<'a, Fut> {
+    future: Pin<&'a mut Fut>,
+    stopped: &'a mut Receiver<()>,
+}
+
+impl<Fut: Future> Future for Watched<'_, Fut> {
+    type Output = Watch<Fut::Output>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Poll::Ready(output) = self.future.as_mut().poll(cx) {
+            return Poll::Ready(Watch::Finished(output));
+        }
+        match Pin::new(&mut *self.stopped).poll_next(cx) {
+            Poll::Ready(signal) => Poll::Ready(Watch::Stopped(signal.is_some())),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// Why `StoppableThreadPool::try_spawn()` did not spawn a future, which is handed back un-polled.
+pub enum TrySpawnError<Fut> {
+    /// The pool already has as many unfinished tasks as allowed by `set_pending_limit()`.
+    Full(Fut),
+    /// The pool is stopping, draining or finished.
+    Stopped(Fut),
+}
+
+impl<Fut> TrySpawnError<Fut> {
+    /// Get the future back.
+    pub fn into_inner(self) -> Fut {
+        match self {
+            TrySpawnError::Full(future) | TrySpawnError::Stopped(future) => future,
+        }
+    }
+}
+
+impl<Fut> fmt::Debug for TrySpawnError<Fut> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrySpawnError::Full(_) => write!(f, "Full(..)"),
+            TrySpawnError::Stopped(_) => write!(f, "Stopped(..)"),
+        }
+    }
+}
+
+impl<Fut> fmt::Display for TrySpawnError<Fut> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrySpawnError::Full(_) => write!(f, "pool has too many pending tasks"),
+            TrySpawnError::Stopped(_) => write!(f, "pool is stopping"),
+        }
+    }
+}
+
+impl<Fut> Error for TrySpawnError<Fut> {}
+
+/// The pool was being observed, so `StoppableThreadPool::with_pool()` did not swap the executor.
+pub struct SwapPoolError {
+    pub(crate) pool: ThreadPool,
+}
+
+impl SwapPoolError {
+    /// Get the rejected executor back.
+    pub fn into_pool(self) -> ThreadPool {
+        self.pool
+    }
+}
+
+impl fmt::Debug for SwapPoolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SwapPoolError").finish_non_exhaustive()
+    }
+}
+
+impl fmt::Display for SwapPoolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "pool is being observed")
+    }
+}
+
+impl Error for SwapPoolError {}
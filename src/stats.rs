@@ -0,0 +1,79 @@
+use std::time::Duration;
+
+/// Aggregate of the run times of a pool's tasks, as returned by `StoppableThreadPool::durations()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskDurations {
+    count: usize,
+    min: Duration,
+    max: Duration,
+    total: Duration,
+}
+
+impl TaskDurations {
+    pub(crate) fn from_durations<I>(durations: I) -> Option<TaskDurations>
+    where
+        I: IntoIterator<Item = Duration>,
+    {
+        durations.into_iter().fold(None, |stats, duration| {
+            Some(match stats {
+                None => TaskDurations {
+                    count: 1,
+                    min: duration,
+                    max: duration,
+                    total: duration,
+                },
+                Some(stats) => TaskDurations {
+                    count: stats.count + 1,
+                    min: stats.min.min(duration),
+                    max: stats.max.max(duration),
+                    total: stats.total + duration,
+                },
+            })
+        })
+    }
+
+    /// Number of tasks which finished running.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Run time of the fastest task.
+    pub fn min(&self) -> Duration {
+        self.min
+    }
+
+    /// Run time of the slowest task.
+    pub fn max(&self) -> Duration {
+        self.max
+    }
+
+    /// Mean run time of all tasks.
+    pub fn mean(&self) -> Duration {
+        // Dividing by a `u32` would truncate the count.
+        Duration::from_nanos((self.total.as_nanos() / self.count as u128) as u64)
+    }
+
+    /// Sum of the run times of all tasks.
+    pub fn total(&self) -> Duration {
+        self.total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::TaskDurations;
+
+    #[test]
+    fn mean_of_many_durations() {
+        let count = u32::MAX as usize + 2;
+        let stats = TaskDurations {
+            count,
+            min: Duration::from_secs(1),
+            max: Duration::from_secs(1),
+            total: Duration::from_secs(count as u64),
+        };
+        assert_eq!(stats.mean(), Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,56 @@
+use futures::future::{BoxFuture, FutureExt};
+
+use crate::{StoppableThreadPool, TaskId};
+
+/// The core surface of a `StoppableThreadPool` as an object-safe trait, so code taking a pool can be handed a test double instead.
+///
+/// See `MockPool` behind the `test-util` feature for a double which records the spawned futures.
+pub trait StoppablePool<PoolError, StopReason = PoolError>: Send + Sync {
+    /// Spawn a boxed future, see `StoppableThreadPool::spawn()`.
+    fn spawn_boxed(&mut self, future: BoxFuture<'static, Result<(), PoolError>>) -> TaskId;
+
+    /// Observe the pool, see `StoppableThreadPool::observe()`.
+    fn observe(&self) -> BoxFuture<'_, Result<(), PoolError>>;
+
+    /// Stop the pool, see `StoppableThreadPool::stop()`.
+    fn stop(&self, why: StopReason) -> BoxFuture<'_, ()>;
+}
+
+impl<PoolError, StopReason> StoppablePool<PoolError, StopReason>
+    for StoppableThreadPool<PoolError, StopReason>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Into<PoolError> + Send + Sync + 'static,
+{
+    fn spawn_boxed(&mut self, future: BoxFuture<'static, Result<(), PoolError>>) -> TaskId {
+        self.spawn_task(future, None)
+    }
+
+    fn observe(&self) -> BoxFuture<'_, Result<(), PoolError>> {
+        StoppableThreadPool::observe(self).boxed()
+    }
+
+    fn stop(&self, why: StopReason) -> BoxFuture<'_, ()> {
+        StoppableThreadPool::stop(self, why).boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{executor::block_on, future::pending, FutureExt};
+
+    use super::StoppablePool;
+    use crate::StoppableThreadPool;
+
+    fn run(pool: &mut dyn StoppablePool<String>) -> Result<(), String> {
+        pool.spawn_boxed(pending().boxed());
+        pool.spawn_boxed(async { Err("fail".to_string()) }.boxed());
+        block_on(pool.observe())
+    }
+
+    #[test]
+    fn dyn_pool() {
+        let mut pool = StoppableThreadPool::new().unwrap();
+        assert_eq!(run(&mut pool), Err("fail".to_string()));
+    }
+}
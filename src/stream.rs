@@ -0,0 +1,143 @@
+use std::sync::atomic::Ordering;
+
+use async_std::channel::unbounded;
+
+use futures::{
+    future::{Future, FutureExt},
+    pin_mut, select,
+    stream::{Stream, StreamExt},
+};
+
+use crate::{Spawner, StoppableThreadPool, TaskOptions};
+
+impl<PoolError, StopReason> StoppableThreadPool<PoolError, StopReason>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Into<PoolError> + Send + Sync + 'static,
+{
+    /// Spawn a task running `f` for every item of `stream`, with at most `limit` of them in flight at once, and observe the pool.
+    ///
+    /// Like `StreamExt::for_each_concurrent()`, but a failing handler or a call to `stop()` stops the handlers in flight and the stream is no longer polled.
+    /// Tasks spawned to the pool otherwise are observed along with the handlers.
+    /// A `limit` of zero is treated as one.
+    pub async fn for_each_concurrent<S, F, Fut>(
+        &mut self,
+        stream: S,
+        limit: usize,
+        mut f: F,
+    ) -> Result<(), PoolError>
+    where
+        S: Stream,
+        F: FnMut(S::Item) -> Fut,
+        Fut: Future<Output = Result<(), PoolError>> + Send + 'static,
+    {
+        let limit = limit.max(1);
+        let spawner = &self.spawner;
+        // Observing must not succeed in between two items, so the stream counts as outstanding until it is exhausted or dropped.
+        spawner.shared.outstanding.fetch_add(1, Ordering::AcqRel);
+        let outstanding = Outstanding(spawner);
+        let feed = async move {
+            let _outstanding = outstanding;
+            let (done_tx, done_rx) = unbounded::<()>();
+            let mut in_flight = 0;
+            pin_mut!(stream);
+            loop {
+                while in_flight >= limit {
+                    let _ = done_rx.recv().await;
+                    in_flight -= 1;
+                }
+                if spawner.shared.stopping.load(Ordering::Acquire) {
+                    break;
+                }
+                let item = match stream.next().await {
+                    Some(item) => item,
+                    None => break,
+                };
+                let done = done_tx.clone();
+                spawner.spawn(
+                    f(item),
+                    TaskOptions {
+                        on_outcome: Some(Box::new(move |_| {
+                            let _ = done.try_send(());
+                        })),
+                        ..TaskOptions::default()
+                    },
+                );
+                in_flight += 1;
+            }
+        };
+
+        let observe = self.observe().fuse();
+        let feed = feed.fuse();
+        pin_mut!(observe, feed);
+        loop {
+            select! {
+                result = observe => return result,
+                () = feed => {},
+            }
+        }
+    }
+}
+
+struct Outstanding<'a, PoolError, StopReason>(&'a Spawner<PoolError, StopReason>);
+
+impl<PoolError, StopReason> Drop for Outstanding<'_, PoolError, StopReason> {
+    fn drop(&mut self) {
+        self.0.shared.finish_outstanding(&self.0.control_sender);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use futures::{
+        executor::block_on,
+        future::pending,
+        stream::{self, StreamExt},
+    };
+
+    use crate::StoppableThreadPool;
+
+    #[test]
+    fn for_each_concurrent() {
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        let (running, max_running) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        let result = block_on(pool.for_each_concurrent(stream::iter(0..50), 4, |_| {
+            let (running, max_running) = (running.clone(), max_running.clone());
+            async move {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                max_running.fetch_max(now, Ordering::SeqCst);
+                async_std::task::sleep(std::time::Duration::from_millis(1)).await;
+                running.fetch_sub(1, Ordering::SeqCst);
+                Ok(())
+            }
+        }));
+        assert_eq!(result, Ok(()));
+        assert!(max_running.load(Ordering::SeqCst) <= 4);
+    }
+
+    #[test]
+    fn for_each_concurrent_stops_stream() {
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        let pulled = Arc::new(AtomicUsize::new(0));
+        let items = {
+            let pulled = pulled.clone();
+            stream::iter(0..).inspect(move |_| {
+                pulled.fetch_add(1, Ordering::SeqCst);
+            })
+        };
+        let result = block_on(pool.for_each_concurrent(items, 4, |item| async move {
+            match item {
+                3 => Err("fail".to_string()),
+                _ => pending().await,
+            }
+        }));
+        assert_eq!(result, Err("fail".to_string()));
+        // The failed handler may free its slot for one more item before the pool stops.
+        assert!(pulled.load(Ordering::SeqCst) <= 5);
+    }
+}
@@ -0,0 +1,182 @@
+//! Metrics emitted through the `metrics` facade, see the `metrics` feature.
+//!
+//! The handles are registered once per pool, so emitting is a few atomic operations on the hot path.
+//! All metrics are labelled with `pool`, the name of the pool or `unnamed`:
+//!
+//! * `poolparty_tasks_spawned_total`: counter of spawned tasks
+//! * `poolparty_tasks_completed_total`: counter of finished tasks, labelled with `outcome`
+//! * `poolparty_tasks_in_flight`: gauge of spawned tasks which did not finish yet
+//! * `poolparty_stop_requests_total`: counter of stops acted upon by `observe()`
+//! * `poolparty_time_to_stop_seconds`: histogram of the time from the stop broadcast until each cancelled task finished
+
+use std::sync::Mutex;
+
+use metrics::{counter, gauge, histogram, Counter, Gauge, Histogram};
+
+use crate::{clock::Instant, OutcomeKind};
+
+pub(crate) struct PoolMetrics {
+    spawned: Counter,
+    completed: [Counter; 5],
+    in_flight: Gauge,
+    stop_requests: Counter,
+    time_to_stop: Histogram,
+    /// When the stop signal was first broadcast.
+    stopped_at: Mutex<Option<Instant>>,
+}
+
+impl PoolMetrics {
+    pub(crate) fn new(pool: Option<&str>) -> PoolMetrics {
+        let pool = pool.unwrap_or("unnamed").to_string();
+        let completed = |outcome: &'static str| counter!("poolparty_tasks_completed_total", "pool" => pool.clone(), "outcome" => outcome);
+        PoolMetrics {
+            spawned: counter!("poolparty_tasks_spawned_total", "pool" => pool.clone()),
+            completed: [
+                completed("completed"),
+                completed("failed"),
+                completed("cancelled"),
+                completed("panicked"),
+                completed("timed_out"),
+            ],
+            in_flight: gauge!("poolparty_tasks_in_flight", "pool" => pool.clone()),
+            stop_requests: counter!("poolparty_stop_requests_total", "pool" => pool.clone()),
+            time_to_stop: histogram!("poolparty_time_to_stop_seconds", "pool" => pool),
+            stopped_at: Mutex::new(None),
+        }
+    }
+
+    pub(crate) fn spawned(&self) {
+        self.spawned.increment(1);
+        self.in_flight.increment(1.0);
+    }
+
+    pub(crate) fn finished(&self, outcome: OutcomeKind, now: Instant) {
+        let index = match outcome {
+            OutcomeKind::Completed => 0,
+            OutcomeKind::Failed => 1,
+            OutcomeKind::Cancelled => 2,
+            OutcomeKind::Panicked => 3,
+            OutcomeKind::TimedOut => 4,
+        };
+        self.completed[index].increment(1);
+        self.in_flight.decrement(1.0);
+        if outcome == OutcomeKind::Cancelled {
+            if let Some(stopped_at) = *self.stopped_at.lock().unwrap() {
+                self.time_to_stop
+                    .record(now.saturating_duration_since(stopped_at));
+            }
+        }
+    }
+
+    pub(crate) fn stop_requested(&self) {
+        self.stop_requests.increment(1);
+    }
+
+    pub(crate) fn stop_broadcast(&self, now: Instant) {
+        self.stopped_at.lock().unwrap().get_or_insert(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{atomic::AtomicU64, atomic::Ordering, Arc, Mutex},
+    };
+
+    use futures::{executor::block_on, future::pending};
+    use metrics::{
+        Counter, Gauge, Histogram, Key, KeyName, Metadata, Recorder, SharedString, Unit,
+    };
+
+    use crate::StoppableThreadPool;
+
+    /// Keeps counters and gauges by their name and labels.
+    #[derive(Default)]
+    struct TestRecorder {
+        values: Mutex<HashMap<String, Arc<AtomicU64>>>,
+    }
+
+    impl TestRecorder {
+        fn value(&self, key: &Key) -> Arc<AtomicU64> {
+            let labels: Vec<String> = key
+                .labels()
+                .map(|label| format!("{}={}", label.key(), label.value()))
+                .collect();
+            let name = format!("{}{{{}}}", key.name(), labels.join(","));
+            self.values.lock().unwrap().entry(name).or_default().clone()
+        }
+
+        fn get(&self, name: &str) -> u64 {
+            let values = self.values.lock().unwrap();
+            values[name].load(Ordering::SeqCst)
+        }
+    }
+
+    impl Recorder for TestRecorder {
+        fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+        fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+            Counter::from_arc(self.value(key))
+        }
+
+        fn register_gauge(&self, key: &Key, _: &Metadata<'_>) -> Gauge {
+            Gauge::from_arc(self.value(key))
+        }
+
+        fn register_histogram(&self, _: &Key, _: &Metadata<'_>) -> Histogram {
+            Histogram::noop()
+        }
+    }
+
+    #[test]
+    fn pool_metrics() {
+        let recorder = TestRecorder::default();
+        let mut pool = StoppableThreadPool::<String>::named("ingest").unwrap();
+        metrics::with_local_recorder(&recorder, || {
+            pool.spawn(async { Ok(()) })
+                .spawn(pending())
+                .spawn(async { Err("fail".to_string()) });
+        });
+
+        assert_eq!(block_on(pool.observe()), Err("fail".to_string()));
+        assert_eq!(
+            recorder.get("poolparty_tasks_spawned_total{pool=ingest}"),
+            3
+        );
+        assert_eq!(
+            recorder.get("poolparty_tasks_completed_total{pool=ingest,outcome=failed}"),
+            1
+        );
+        assert_eq!(
+            recorder.get("poolparty_stop_requests_total{pool=ingest}"),
+            1
+        );
+    }
+
+    #[test]
+    fn stop_ok_counted() {
+        let recorder = TestRecorder::default();
+        let mut pool = StoppableThreadPool::<String>::named("drain").unwrap();
+        metrics::with_local_recorder(&recorder, || {
+            pool.spawn(pending());
+        });
+        block_on(async {
+            pool.stop_ok().await;
+            assert_eq!(pool.observe().await, Ok(()));
+        });
+
+        let mut pool = StoppableThreadPool::<String>::named("drain").unwrap();
+        metrics::with_local_recorder(&recorder, || {
+            pool.spawn(pending())
+                .spawn_with_control(|control| async move {
+                    control.stop_ok();
+                    pending().await
+                });
+        });
+        assert_eq!(block_on(pool.observe()), Ok(()));
+        assert_eq!(recorder.get("poolparty_stop_requests_total{pool=drain}"), 2);
+    }
+}
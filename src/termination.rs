@@ -0,0 +1,199 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    task::{Poll, Waker},
+    time::Duration,
+};
+
+use futures::future::{poll_fn, Future};
+
+use crate::{clock::timeout, StoppableThreadPool, Task, TaskId};
+
+/// Wakes `await_stopped()` whenever a task wrapper exits.
+#[derive(Default)]
+pub(crate) struct Exits {
+    waiters: Mutex<Vec<Waker>>,
+}
+
+/// Marks a task as exited when its wrapper is dropped, however it ended.
+pub(crate) struct ExitGuard {
+    pub(crate) exited: Arc<AtomicBool>,
+    pub(crate) exits: Arc<Exits>,
+}
+
+impl Drop for ExitGuard {
+    fn drop(&mut self) {
+        self.exited.store(true, Ordering::Release);
+        let waiters = std::mem::take(&mut *self.exits.waiters.lock().unwrap());
+        for waiter in waiters {
+            waiter.wake();
+        }
+    }
+}
+
+impl<PoolError, StopReason> StoppableThreadPool<PoolError, StopReason>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Send + Sync + 'static,
+{
+    /// Wait up to `deadline` for every task to exit, returning the tasks still alive with their contexts if they did not.
+    ///
+    /// Meant to be called after the pool was stopped: a task which does not exit in time is stuck in a poll that does not yield.
+    /// A task has exited once its future and any cleanup were dropped, wherever it was in its execution.
+    pub async fn await_stopped(
+        &self,
+        deadline: Duration,
+    ) -> Result<(), Vec<(TaskId, Option<String>)>> {
+        let shared = &self.spawner.shared;
+        if timeout(&*shared.clock, deadline, self.all_exited())
+            .await
+            .is_some()
+        {
+            return Ok(());
+        }
+        let tasks = shared.tasks.lock().unwrap();
+        Err(tasks
+            .iter()
+            .enumerate()
+            .filter(|(_, task)| !task.exited.load(Ordering::Acquire))
+            .map(|(id, task)| (TaskId(id), task.context.clone()))
+            .collect())
+    }
+
+    /// Wait until every task wrapper finished executing, including the tasks cancelled by the stop broadcast.
+    ///
+    /// `observe()` returns once the stop signal was sent, this resolves once the tasks are actually gone,
+    /// so resources they hold can be torn down safely. With a `deadline` this is the same as `await_stopped()`,
+    /// without one a task that never yields keeps this waiting forever.
+    pub async fn await_termination(
+        &self,
+        deadline: Option<Duration>,
+    ) -> Result<(), Vec<(TaskId, Option<String>)>> {
+        match deadline {
+            Some(deadline) => self.await_stopped(deadline).await,
+            None => {
+                self.all_exited().await;
+                Ok(())
+            }
+        }
+    }
+
+    fn all_exited(&self) -> impl Future<Output = ()> + '_ {
+        self.exited(None)
+    }
+
+    /// Resolves once the tasks `among`, or all tasks if `None`, exited.
+    pub(crate) fn exited<'a>(
+        &'a self,
+        among: Option<&'a [TaskId]>,
+    ) -> impl Future<Output = ()> + 'a {
+        let shared = &self.spawner.shared;
+        poll_fn(move |cx| {
+            // Registering before checking can't miss a task exiting in between.
+            {
+                let mut waiters = shared.exits.waiters.lock().unwrap();
+                // After a spurious wakeup the waker of the last poll is still registered.
+                if !waiters.iter().any(|waiter| waiter.will_wake(cx.waker())) {
+                    waiters.push(cx.waker().clone());
+                }
+            }
+            let tasks = shared.tasks.lock().unwrap();
+            let exited = |task: &Task| task.exited.load(Ordering::Acquire);
+            let all = match among {
+                Some(ids) => ids.iter().all(|id| exited(&tasks[id.0])),
+                None => tasks.iter().all(exited),
+            };
+            match all {
+                true => Poll::Ready(()),
+                false => Poll::Pending,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        task::Context,
+        time::Duration,
+    };
+
+    use futures::{
+        executor::{block_on, ThreadPool},
+        future::{pending, Future},
+        task::noop_waker_ref,
+    };
+
+    use crate::{StoppableThreadPool, TaskId};
+
+    #[test]
+    fn wedged_task() {
+        // The wedged task blocks one thread, the other one is left to stop the remaining task.
+        let threads = ThreadPool::builder().pool_size(2).create().unwrap();
+        let mut pool = StoppableThreadPool::<String>::new_with_pool(threads);
+        let (tx, rx) = std::sync::mpsc::channel();
+        let (started, wedged) = std::sync::mpsc::channel();
+        pool.spawn(pending())
+            .spawn_with_context("wedged", async move {
+                started.send(()).unwrap();
+                // Blocks the executor thread without ever yielding.
+                let _ = rx.recv();
+                Ok(())
+            });
+        wedged.recv().unwrap();
+
+        block_on(async {
+            pool.stop("stop".to_string()).await;
+            assert_eq!(pool.observe().await, Err("stop".to_string()));
+            assert_eq!(
+                pool.await_stopped(Duration::from_millis(100)).await,
+                Err(vec![(TaskId(1), Some("wedged".to_string()))])
+            );
+            tx.send(()).unwrap();
+            assert_eq!(pool.await_stopped(Duration::from_secs(5)).await, Ok(()));
+        });
+    }
+
+    #[test]
+    fn waker_registered_once() {
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        pool.spawn(pending());
+        let mut exited = Box::pin(pool.all_exited());
+        let mut cx = Context::from_waker(noop_waker_ref());
+        for _ in 0..10 {
+            assert!(exited.as_mut().poll(&mut cx).is_pending());
+        }
+        assert_eq!(pool.spawner.shared.exits.waiters.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn terminated_tasks_released() {
+        struct Resource(Arc<AtomicBool>);
+        impl Drop for Resource {
+            fn drop(&mut self) {
+                self.0.store(true, Ordering::SeqCst);
+            }
+        }
+
+        let released = Arc::new(AtomicBool::new(false));
+        let resource = Resource(released.clone());
+        let mut pool = StoppableThreadPool::<String>::new().unwrap();
+        pool.spawn(async move {
+            let _resource = resource;
+            pending().await
+        });
+
+        block_on(async {
+            pool.stop("stop".to_string()).await;
+            assert_eq!(pool.observe().await, Err("stop".to_string()));
+            assert_eq!(pool.await_termination(None).await, Ok(()));
+        });
+        assert!(released.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,179 @@
+use std::{io, marker::PhantomData};
+
+use futures::future::Future;
+
+use crate::{Failure, PoolObserver, Progress, StoppableThreadPool, TaskDurations, TaskId, Warning};
+
+/// State of a `Pool` which still accepts tasks.
+#[derive(Debug)]
+pub struct Building;
+
+/// State of a `Pool` whose tasks were launched, it can only be observed or stopped.
+#[derive(Debug)]
+pub struct Running;
+
+/// A `StoppableThreadPool` whose state is tracked at compile time.
+///
+/// Tasks can only be spawned on a `Pool<Building, _>`, `finish()` turns it into a `Pool<Running, _>` which can only be observed and stopped:
+///
+/// ```compile_fail
+/// use poolparty::{Building, Pool};
+///
+/// let mut pool = Pool::<Building, String>::new().unwrap().finish();
+/// pool.spawn(async { Ok(()) });
+/// ```
+pub struct Pool<State, PoolError, StopReason = PoolError>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Send + Sync + 'static,
+{
+    inner: StoppableThreadPool<PoolError, StopReason>,
+    state: PhantomData<State>,
+}
+
+impl<PoolError> Pool<Building, PoolError>
+where
+    PoolError: Send + Sync + 'static,
+{
+    /// Same as `StoppableThreadPool::new()`.
+    pub fn new() -> Result<Self, io::Error> {
+        StoppableThreadPool::new().map(Pool::from)
+    }
+}
+
+impl<PoolError, StopReason> From<StoppableThreadPool<PoolError, StopReason>>
+    for Pool<Building, PoolError, StopReason>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Send + Sync + 'static,
+{
+    fn from(inner: StoppableThreadPool<PoolError, StopReason>) -> Self {
+        Pool {
+            inner,
+            state: PhantomData,
+        }
+    }
+}
+
+impl<PoolError, StopReason> Pool<Building, PoolError, StopReason>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Send + Sync + 'static,
+{
+    /// Same as `StoppableThreadPool::spawn()`.
+    pub fn spawn<Fut>(&mut self, future: Fut) -> &mut Self
+    where
+        Fut: Future<Output = Result<(), PoolError>> + Send + 'static,
+    {
+        self.inner.spawn(future);
+        self
+    }
+
+    /// Same as `StoppableThreadPool::spawn_with_context()`.
+    pub fn spawn_with_context<Fut>(&mut self, context: impl Into<String>, future: Fut) -> &mut Self
+    where
+        Fut: Future<Output = Result<(), PoolError>> + Send + 'static,
+    {
+        self.inner.spawn_with_context(context, future);
+        self
+    }
+
+    /// Same as `StoppableThreadPool::add()`, the future is launched by `finish()`.
+    pub fn add<Fut>(&mut self, future: Fut) -> &mut Self
+    where
+        Fut: Future<Output = Result<(), PoolError>> + Send + 'static,
+    {
+        self.inner.add(future);
+        self
+    }
+
+    /// Launch the added futures, no more tasks can be spawned afterwards.
+    pub fn finish(mut self) -> Pool<Running, PoolError, StopReason> {
+        self.inner.start();
+        Pool {
+            inner: self.inner,
+            state: PhantomData,
+        }
+    }
+}
+
+impl<PoolError, StopReason> Pool<Running, PoolError, StopReason>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Send + Sync + 'static,
+{
+    /// Same as `StoppableThreadPool::observe()`.
+    pub async fn observe(&self) -> Result<(), PoolError>
+    where
+        StopReason: Into<PoolError>,
+    {
+        self.inner.observe().await
+    }
+
+    /// Same as `StoppableThreadPool::observe_detailed()`.
+    pub async fn observe_detailed(&self) -> Result<(), Failure<PoolError, StopReason>> {
+        self.inner.observe_detailed().await
+    }
+
+    /// Same as `StoppableThreadPool::stop()`.
+    pub async fn stop(&self, why: StopReason) {
+        self.inner.stop(why).await
+    }
+
+    /// Same as `StoppableThreadPool::observer()`.
+    pub fn observer(&self) -> PoolObserver<PoolError, StopReason> {
+        self.inner.observer()
+    }
+
+    /// Same as `StoppableThreadPool::is_stopping()`.
+    pub fn is_stopping(&self) -> bool {
+        self.inner.is_stopping()
+    }
+
+    /// Same as `StoppableThreadPool::is_finished()`.
+    pub fn is_finished(&self) -> bool {
+        self.inner.is_finished()
+    }
+
+    /// Same as `StoppableThreadPool::failed_task()`.
+    pub fn failed_task(&self) -> Option<TaskId> {
+        self.inner.failed_task()
+    }
+
+    /// Same as `StoppableThreadPool::task_context()`.
+    pub fn task_context(&self, id: TaskId) -> Option<String> {
+        self.inner.task_context(id)
+    }
+
+    /// Same as `StoppableThreadPool::durations()`.
+    pub fn durations(&self) -> Option<TaskDurations> {
+        self.inner.durations()
+    }
+
+    /// Same as `StoppableThreadPool::warnings()`.
+    pub fn warnings(&self) -> Vec<Warning> {
+        self.inner.warnings()
+    }
+
+    /// The current progress, see `StoppableThreadPool::progress()`.
+    pub fn progress(&self) -> Progress {
+        self.inner.progress().get()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+
+    use super::{Building, Pool};
+
+    #[test]
+    fn build_then_observe() {
+        let mut pool = Pool::<Building, String>::new().unwrap();
+        pool.spawn(async { Ok(()) })
+            .add(async { Err("fail".to_string()) });
+        let pool = pool.finish();
+        assert_eq!(block_on(pool.observe()), Err("fail".to_string()));
+        assert!(pool.failed_task().is_some());
+    }
+}
@@ -0,0 +1,27 @@
+use futures::future::Future;
+
+use crate::{StoppableThreadPool, TaskId, TaskOptions};
+
+impl<PoolError, StopReason> StoppableThreadPool<PoolError, StopReason>
+where
+    PoolError: Send + Sync + 'static,
+    StopReason: Send + Sync + 'static,
+{
+    /// Spawn a future which is not `Send` onto the JavaScript event loop, through `wasm_bindgen_futures::spawn_local()`.
+    ///
+    /// The task is observed and stopped like any other, whatever executor the pool uses for the rest of its tasks,
+    /// so futures holding JavaScript values can be mixed with the ones spawned by `spawn()`. It only works on wasm targets, elsewhere this panics.
+    pub fn spawn_local<Fut>(&mut self, future: Fut) -> TaskId
+    where
+        Fut: Future<Output = Result<(), PoolError>> + 'static,
+    {
+        let (id, registration) = self.spawner.register(None);
+        if let Some((stopped, state)) = registration {
+            let wrapper = self
+                .spawner
+                .wrap(id, stopped, state, future, TaskOptions::default());
+            wasm_bindgen_futures::spawn_local(wrapper);
+        }
+        id
+    }
+}
@@ -0,0 +1,7 @@
+use poolparty::{Building, Pool};
+
+fn main() {
+    let pool = Pool::<Building, String>::new().unwrap();
+    let mut pool = pool.finish();
+    pool.add(async { Ok(()) });
+}
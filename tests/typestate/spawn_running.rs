@@ -0,0 +1,6 @@
+use poolparty::{Building, Pool};
+
+fn main() {
+    let mut pool = Pool::<Building, String>::new().unwrap().finish();
+    pool.spawn(async { Ok(()) });
+}
@@ -0,0 +1,65 @@
+#![cfg(all(feature = "wasm", target_arch = "wasm32"))]
+
+use std::{rc::Rc, time::Duration};
+
+use futures::{future::pending, join};
+use poolparty::{ExecutionMode, StoppableThreadPool};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+async fn ok() -> Result<(), String> {
+    async_std::task::yield_now().await;
+    Ok(())
+}
+
+async fn fail(msg: String) -> Result<(), String> {
+    Err(msg)
+}
+
+#[wasm_bindgen_test]
+async fn observe_ok() {
+    let mut pool = StoppableThreadPool::new_wasm();
+    assert_eq!(pool.execution_mode(), ExecutionMode::Wasm);
+    for _ in 0..100 {
+        pool.spawn(ok());
+    }
+    assert_eq!(pool.observe().await, Ok(()));
+}
+
+#[wasm_bindgen_test]
+async fn observe_err() {
+    let mut pool = StoppableThreadPool::new_wasm();
+    pool.spawn(fail("fail".to_string())).spawn(pending());
+    assert_eq!(pool.observe().await, Err("fail".to_string()));
+}
+
+#[wasm_bindgen_test]
+async fn user_stopped() {
+    let mut pool = StoppableThreadPool::new_wasm();
+    pool.spawn(pending()).spawn(pending());
+    let (result, ()) = join!(pool.observe(), pool.stop("stop".to_string()));
+    assert_eq!(result, Err("stop".to_string()));
+}
+
+#[wasm_bindgen_test]
+async fn not_send() {
+    let mut pool = StoppableThreadPool::<String>::new_wasm();
+    let value = Rc::new(1);
+    pool.spawn_local(async move {
+        async_std::task::yield_now().await;
+        match *value {
+            1 => Ok(()),
+            _ => Err("unexpected value".to_string()),
+        }
+    });
+    pool.spawn_local(pending());
+    let (result, ()) = join!(pool.observe(), pool.stop("stop".to_string()));
+    assert_eq!(result, Err("stop".to_string()));
+}
+
+#[wasm_bindgen_test]
+async fn timed_out() {
+    let mut pool = StoppableThreadPool::<String>::new_wasm();
+    // Only completes once the timer of the event loop fired.
+    pool.spawn_with_timeout(Duration::from_millis(10), pending());
+    assert_eq!(pool.observe().await, Ok(()));
+}